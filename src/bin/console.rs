@@ -0,0 +1,217 @@
+//! Plays the puzzle as a line-based console. Reads lines of the game's move
+//! keys from stdin and acts on each line once Enter is pressed, so it works
+//! over SSH and from scripts:
+//!
+//!     echo "wwdrs" | console --plain --seed 7
+
+use std::io::{self, BufRead, Write};
+
+use magic_bean::puzzle::{self, History, Move, Puzzle, SolveMode};
+use magic_bean::puzzle::{GROUP_NAMES, LOOP_LEN, SCRAMBLE_MOVES, WINDOW_LEN};
+
+const HELP: &str = "\
+w s  turn the front loop under the blocks
+a d  slide the blocks left or right
+r t  rotate the block window front to back
+q    flip to the other face
+u    undo (also backspace)
+p    scramble
+l    reset to solved
+o    toggle numbered solve mode
+c    print the state code
+h    this help
+x    quit";
+
+/// Ball colors of the default theme.
+const COLORS: [(u8, u8, u8); 4] = [(153, 51, 26), (51, 153, 26), (26, 51, 153), (153, 51, 153)];
+
+struct Options {
+    seed: Option<u64>,
+    code: Option<String>,
+    plain: bool,
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Options { seed: None, code: None, plain: false };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed needs a number")?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("bad seed {}", seed))?);
+                }
+                "--code" => options.code = Some(args.next().ok_or("--code needs a state code")?),
+                "--plain" => options.plain = true,
+                "-h" | "--help" => {
+                    println!("usage: console [--seed N] [--code CODE] [--plain]\n\n{}", HELP);
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+struct Game {
+    history: History,
+    mode: SolveMode,
+    is_face_back: bool,
+    rng: fastrand::Rng,
+    plain: bool,
+}
+
+impl Game {
+    fn puzzle(&self) -> &Puzzle {
+        self.history.current()
+    }
+
+    /// The move of `key`, mapped like the game's keyboard input.
+    fn key_move(&self, key: char) -> Option<Move> {
+        let front = self.puzzle().block_groups()[self.is_face_back as usize];
+        let m = match key {
+            'w' => Move::Turn { group: front, forward: !self.is_face_back },
+            's' => Move::Turn { group: front, forward: self.is_face_back },
+            'a' => Move::Slide { right: false },
+            'd' => Move::Slide { right: true },
+            'r' | 't' => Move::Rotate,
+            _ => return None,
+        };
+        Some(m)
+    }
+
+    /// Handles one key, returns `false` to quit.
+    fn key(&mut self, key: char, out: &mut impl Write) -> io::Result<bool> {
+        if let Some(m) = self.key_move(key) {
+            let mut puzzle = self.puzzle().clone();
+            match puzzle.apply(m) {
+                Ok(()) => {
+                    let was_solved = self.puzzle().is_solved(self.mode);
+                    self.history.push(puzzle);
+                    if !was_solved && self.puzzle().is_solved(self.mode) {
+                        writeln!(out, "solved in {} moves!", self.history.moves())?;
+                    }
+                }
+                Err(e) => writeln!(out, "{}: {}", m, e)?,
+            }
+            return Ok(true);
+        }
+
+        match key {
+            'q' => self.is_face_back = !self.is_face_back,
+            'u' | '\u{8}' | '\u{7f}' => {
+                if self.history.undo().is_none() {
+                    writeln!(out, "nothing to undo")?;
+                }
+            }
+            'p' => {
+                let mut puzzle = Puzzle::solved();
+                puzzle.scramble(&self.rng, SCRAMBLE_MOVES);
+                self.history = History::new(puzzle);
+            }
+            'l' => self.history = History::default(),
+            'o' => {
+                self.mode = match self.mode {
+                    SolveMode::Colors => SolveMode::Numbered,
+                    SolveMode::Numbered => SolveMode::Colors,
+                };
+            }
+            'c' => writeln!(out, "{}", self.puzzle().code())?,
+            'h' | '?' => writeln!(out, "{}", HELP)?,
+            'x' => return Ok(false),
+            c if c.is_whitespace() => {}
+            c => writeln!(out, "unknown key '{}', h for help", c)?,
+        }
+        Ok(true)
+    }
+
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        let puzzle = self.puzzle();
+        let face = if self.is_face_back { "back" } else { "front" };
+        let side = if puzzle.is_block_left() { "left" } else { "right" };
+        let solved = if puzzle.is_solved(self.mode) { ", solved" } else { "" };
+        writeln!(out, "moves {}, {} face, blocks {}, {:?}{}", self.history.moves(), face, side, self.mode, solved)?;
+
+        let blocks = puzzle.block_groups();
+        let front = blocks[self.is_face_back as usize];
+        for (group, name) in GROUP_NAMES.iter().enumerate() {
+            let marker = if group == front { '>' } else { ' ' };
+            write!(out, "{} {} ", marker, name)?;
+            for index in 0..LOOP_LEN {
+                let is_window = blocks.contains(&group) && index < WINDOW_LEN;
+                let is_rotate = blocks.contains(&group) && puzzle::ROTATE_WINDOW.contains(&index);
+                let open = if is_window && index == 0 { '[' } else { ' ' };
+                write!(out, "{}{}", open, self.cell(puzzle.get(group, index), is_rotate))?;
+                if is_window && index == WINDOW_LEN - 1 {
+                    write!(out, "]")?;
+                }
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+
+    /// One ball: its color letter, or its number in numbered mode, colored
+    /// unless plain. The rotate window is underlined.
+    fn cell(&self, ball: Option<u8>, is_rotate: bool) -> String {
+        let ball = match ball {
+            Some(ball) => ball,
+            None => return ".".to_string(),
+        };
+        let text = match self.mode {
+            SolveMode::Colors => puzzle::COLOR_CHARS[puzzle::color(ball)].to_string(),
+            SolveMode::Numbered => format!("{:2}", ball),
+        };
+        if self.plain {
+            return text;
+        }
+        let (r, g, b) = COLORS[puzzle::color(ball)];
+        let underline = if is_rotate { ";4" } else { "" };
+        format!("\x1b[1{};38;2;{};{};{}m{}\x1b[0m", underline, r, g, b, text)
+    }
+}
+
+fn main() -> io::Result<()> {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let rng = options.seed.map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed);
+    let start = match &options.code {
+        Some(code) => Puzzle::from_code(code).unwrap_or_else(|e| {
+            eprintln!("bad code: {}", e);
+            std::process::exit(2);
+        }),
+        None => {
+            let mut puzzle = Puzzle::solved();
+            puzzle.scramble(&rng, SCRAMBLE_MOVES);
+            puzzle
+        }
+    };
+    let mut game = Game {
+        history: History::new(start),
+        mode: SolveMode::Colors,
+        is_face_back: false,
+        rng,
+        plain: options.plain,
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "h for help, x to quit")?;
+    game.render(&mut out)?;
+    for line in io::stdin().lock().lines() {
+        for key in line?.chars() {
+            if !game.key(key.to_ascii_lowercase(), &mut out)? {
+                return Ok(());
+            }
+        }
+        game.render(&mut out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Solves positions for the colors mode with `magic_bean::solver`. Takes
+//! positions as arguments or one per line on stdin, each a state code or the
+//! four `group.*` sensor strings:
+//!
+//!     solver --time-limit 5 --format json < scrambles.txt
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use magic_bean::puzzle::{Move, Puzzle};
+use magic_bean::solver::{solve, Limits, Report};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Plain,
+    Json,
+}
+
+struct Options {
+    limits: Limits,
+    format: Format,
+    positions: Vec<String>,
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Options {
+            limits: Limits::default(),
+            format: Format::Plain,
+            positions: Vec::new(),
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+            match arg.as_str() {
+                "--time-limit" => {
+                    let seconds = value("--time-limit")?;
+                    options.limits.time_limit = seconds.parse::<f32>()
+                        .ok()
+                        .filter(|seconds| (0.0..u64::MAX as f32).contains(seconds))
+                        .map(Duration::from_secs_f32)
+                        .ok_or(format!("bad time limit {}", seconds))?;
+                }
+                "--max-depth" => {
+                    let depth = value("--max-depth")?;
+                    options.limits.max_depth = depth.parse().map_err(|_| format!("bad depth {}", depth))?;
+                }
+                "--format" => {
+                    options.format = match value("--format")?.as_str() {
+                        "plain" => Format::Plain,
+                        "json" => Format::Json,
+                        format => return Err(format!("unknown format {}", format)),
+                    };
+                }
+                "-h" | "--help" => {
+                    println!("usage: solver [--time-limit SECONDS] [--max-depth N] [--format plain|json] [POSITION...]");
+                    std::process::exit(0);
+                }
+                _ => options.positions.push(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A solver report for the position of `code`.
+struct Solved {
+    code: String,
+    report: Report,
+}
+
+impl Solved {
+    fn moves(&self) -> Vec<String> {
+        self.report.solution.iter().flatten().map(Move::to_string).collect()
+    }
+
+    fn write(&self, format: Format, out: &mut impl Write) -> io::Result<()> {
+        let report = &self.report;
+        let milliseconds = report.elapsed.as_millis();
+        match format {
+            Format::Plain => {
+                writeln!(out, "position    {}", self.code)?;
+                match &report.solution {
+                    Some(solution) => writeln!(out, "solution    {} moves: {}", solution.len(), self.moves().join(" "))?,
+                    None => writeln!(out, "solution    none found")?,
+                }
+                writeln!(out, "distance    {}{}", if report.is_optimal { "" } else { ">= " }, report.lower_bound)?;
+                writeln!(out, "search      {} nodes in {} ms", report.nodes, milliseconds)?;
+                writeln!(out)
+            }
+            Format::Json => {
+                let moves = self.moves().iter()
+                    .map(|m| format!("\"{}\"", m))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let solution = if report.solution.is_some() { format!("[{}]", moves) } else { "null".to_string() };
+                writeln!(
+                    out,
+                    "{{\"position\":\"{}\",\"solution\":{},\"distance\":{},\"optimal\":{},\"nodes\":{},\"milliseconds\":{}}}",
+                    self.code, solution, report.lower_bound, report.is_optimal, report.nodes, milliseconds,
+                )
+            }
+        }
+    }
+}
+
+fn parse_position(text: &str) -> Result<Puzzle, String> {
+    let puzzle = if text.contains('/') {
+        Puzzle::from_code(text)
+    } else {
+        Puzzle::from_sensors(text)
+    };
+    puzzle.map_err(|e| format!("bad position {}: {}", text.trim(), e))
+}
+
+fn main() -> io::Result<()> {
+    let options = Options::parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    let positions = if options.positions.is_empty() {
+        io::stdin().lock().lines()
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect()
+    } else {
+        options.positions.clone()
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut is_ok = true;
+    for text in positions.iter() {
+        match parse_position(text) {
+            Ok(puzzle) => {
+                let solved = Solved { code: puzzle.code(), report: solve(&puzzle, &options.limits) };
+                solved.write(options.format, &mut out)?;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                is_ok = false;
+            }
+        }
+    }
+    if !is_ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,11 +1,15 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 use bevy::core::FloatOrd;
+use bevy::utils::HashMap;
 
-use crate::util;
+use crate::util::{self, otry};
 use crate::util::range01::{WrappingF32, SaturatingF32};
 
 mod descriptor;
-pub use descriptor::CubeDescriptor;
+pub use descriptor::{BallPlace, CubeDescriptor};
+pub use crate::puzzle::SolveMode;
 
 #[allow(unused)]
 pub mod debug {
@@ -16,13 +20,18 @@ pub mod debug {
 pub struct MainCamera;
 
 pub struct Cube;
+/// Mesh of the cube body under `Cube`.
+pub struct CubeBody;
 pub enum Block { Slide, Rotate }
 pub struct Deco;
 
+/// Cube orientation. `yaw`/`pitch` describe the default locked view, while
+/// `orbit` holds a free arcball rotation when free-orbit mode is on.
 #[derive(Default)]
 pub struct CubeRotation {
     pub yaw: SaturatingF32,
     pub pitch: WrappingF32,
+    pub orbit: Option<Quat>,
 }
 
 impl CubeRotation {
@@ -30,10 +39,52 @@ impl CubeRotation {
         CubeRotation {
             yaw: SaturatingF32::new(yaw),
             pitch: WrappingF32::new(pitch),
+            orbit: None,
+        }
+    }
+
+    pub fn front() -> Self {
+        CubeRotation::new(0.5, -0.05)
+    }
+
+    pub fn flip(&mut self) {
+        if let Some(orbit) = self.orbit {
+            self.orbit = Some(Quat::from_rotation_x(std::f32::consts::PI) * orbit);
+        } else if self.is_face_back() {
+            self.pitch = WrappingF32::new(-0.05);
+        } else {
+            self.pitch = WrappingF32::new(0.45);
         }
     }
 
+    pub fn is_free(&self) -> bool {
+        self.orbit.is_some()
+    }
+
+    pub fn set_free(&mut self, free: bool) {
+        self.orbit = if free {
+            Some(self.to_quat())
+        } else {
+            None
+        };
+    }
+
+    pub fn orbit(&mut self, delta: Vec2) {
+        let orbit = otry!(self.orbit.as_mut());
+        let angle = delta.length();
+        if angle <= f32::EPSILON {
+            return;
+        }
+        // screen space y goes down, so dragging down spins around +x
+        let axis = Vec3::new(delta.y, delta.x, 0.0) / angle;
+        *orbit = (Quat::from_axis_angle(axis, angle) * *orbit).normalize();
+    }
+
     pub fn to_quat(&self) -> Quat {
+        if let Some(orbit) = self.orbit {
+            return orbit;
+        }
+
         const ANGLE30: f32 = std::f32::consts::FRAC_PI_6;
         let yaw = util::bezier::f32::linear(-ANGLE30, ANGLE30, self.yaw.to_f32());
 
@@ -43,16 +94,88 @@ impl CubeRotation {
         Quat::from_rotation_ypr(yaw, pitch, 0.0)
     }
 
+    /// The front face (local +z) points away from the camera.
     pub fn is_face_back(&self) -> bool {
-        let pitch = self.pitch.to_f32();
-        pitch >= 0.25 && pitch <= 0.75
+        (self.to_quat() * Vec3::Z).z < 0.0
     }
 }
 
-#[derive(Clone, Copy)]
+/// Interpolates the displayed cube rotation from `from` toward the current
+/// `CubeRotation` until the timer finishes.
+pub struct ViewAnimation {
+    pub from: Quat,
+    pub timer: Timer,
+}
+
+impl ViewAnimation {
+    pub fn new(from: Quat, config: &ViewConfig) -> Self {
+        ViewAnimation {
+            from,
+            timer: Timer::from_seconds(config.animation_seconds, false),
+        }
+    }
+}
+
+pub struct ViewConfig {
+    pub animation_seconds: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub zoom_speed: f32,
+    /// Scale of the cube rotation per pixel dragged.
+    pub orbit_sensitivity: f32,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        ViewConfig {
+            animation_seconds: 0.3,
+            min_distance: 6.0,
+            max_distance: 18.0,
+            zoom_speed: 0.5,
+            orbit_sensitivity: 1.0,
+        }
+    }
+}
+
+/// See-through cube body and deco, toggled with V.
+#[derive(Clone)]
+pub struct XRayConfig {
+    pub enabled: bool,
+    /// Opacity of the sides facing the camera, `[` and `]` adjust it.
+    pub front_opacity: f32,
+    /// Opacity of the far sides, seen through the front.
+    pub back_opacity: f32,
+    /// Opacity by node name, over the side defaults.
+    pub opacity: HashMap<String, f32>,
+}
+
+impl Default for XRayConfig {
+    fn default() -> Self {
+        XRayConfig {
+            enabled: false,
+            front_opacity: 0.2,
+            back_opacity: 0.5,
+            opacity: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum BallColor { A, B, C, D }
 
 impl BallColor {
+    pub const ALL: [BallColor; 4] = [BallColor::A, BallColor::B, BallColor::C, BallColor::D];
+
+    pub fn index(&self) -> usize {
+        use BallColor::*;
+        match self {
+            A => 0,
+            B => 1,
+            C => 2,
+            D => 3,
+        }
+    }
+
     pub fn to_char(&self) -> char {
         use BallColor::*;
         match self {
@@ -64,13 +187,163 @@ impl BallColor {
     }
 }
 
-#[derive(Bundle)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Palette {
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 5] = [
+        Palette::Default,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+        Palette::HighContrast,
+    ];
+
+    pub fn next(&self) -> Self {
+        let i = Palette::ALL.iter().position(|p| p == self).unwrap();
+        Palette::ALL[(i + 1) % Palette::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        use Palette::*;
+        match self {
+            Default => "default",
+            Deuteranopia => "deuteranopia",
+            Protanopia => "protanopia",
+            Tritanopia => "tritanopia",
+            HighContrast => "high contrast",
+        }
+    }
+
+    /// Ball colors per `BallColor::index`.
+    pub fn colors(&self) -> [Color; 4] {
+        use Palette::*;
+        match self {
+            Default => [
+                Color::rgb(0.6, 0.2, 0.1),
+                Color::rgb(0.2, 0.6, 0.1),
+                Color::rgb(0.1, 0.2, 0.6),
+                Color::rgb(0.6, 0.2, 0.6),
+            ],
+            // blue/orange/yellow/grey, kept apart in lightness as well
+            Deuteranopia => [
+                Color::rgb(0.0, 0.25, 0.6),
+                Color::rgb(0.9, 0.55, 0.0),
+                Color::rgb(0.95, 0.9, 0.4),
+                Color::rgb(0.85, 0.85, 0.85),
+            ],
+            Protanopia => [
+                Color::rgb(0.0, 0.45, 0.7),
+                Color::rgb(0.95, 0.9, 0.25),
+                Color::rgb(0.9, 0.9, 0.9),
+                Color::rgb(0.3, 0.3, 0.3),
+            ],
+            Tritanopia => [
+                Color::rgb(0.85, 0.15, 0.15),
+                Color::rgb(0.0, 0.6, 0.6),
+                Color::rgb(0.95, 0.6, 0.75),
+                Color::rgb(0.25, 0.25, 0.25),
+            ],
+            HighContrast => [
+                Color::rgb(0.9, 0.0, 0.0),
+                Color::rgb(0.95, 0.95, 0.95),
+                Color::rgb(0.05, 0.05, 0.05),
+                Color::rgb(1.0, 0.85, 0.0),
+            ],
+        }
+    }
+}
+
+/// How balls look, applied to `BallMaterials` whenever it changes.
+pub struct BallStyle {
+    pub palette: Palette,
+    /// Print the `BallColor` number on each ball.
+    pub symbols: bool,
+    /// Print the `BallIndex` of each ball.
+    pub labels: bool,
+    /// Colors of `Palette::Default`, set by the theme.
+    pub base_colors: [Color; 4],
+    pub roughness: f32,
+    pub reflectance: f32,
+}
+
+impl Default for BallStyle {
+    fn default() -> Self {
+        BallStyle {
+            palette: Palette::Default,
+            symbols: false,
+            labels: false,
+            base_colors: Palette::Default.colors(),
+            roughness: 0.3,
+            reflectance: 0.2,
+        }
+    }
+}
+
+impl BallStyle {
+    pub fn colors(&self) -> [Color; 4] {
+        match self.palette {
+            Palette::Default => self.base_colors,
+            palette => palette.colors(),
+        }
+    }
+}
+
+/// Symbol textures per `BallColor::index`.
+pub struct BallSymbols {
+    pub textures: Vec<Handle<Texture>>,
+}
+
+/// Ball materials per `BallColor::index`, swapped while highlighting.
+pub struct BallMaterials {
+    pub normal: Vec<Handle<StandardMaterial>>,
+    pub hover: Vec<Handle<StandardMaterial>>,
+    pub grab: Vec<Handle<StandardMaterial>>,
+}
+
+/// Number of the ball, which names its home slot, see `puzzle::home`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct BallIndex(pub usize);
+
+/// Label shell around a ball, showing its `BallIndex`.
+pub struct BallLabel;
+
+pub struct SolvedEvent {
+    pub mode: SolveMode,
+}
+
+/// The balls were put back by an undo, a scramble or a reset, maybe in the
+/// state they were already in.
+pub struct RestoredEvent;
+
+#[derive(Bundle, Clone, Copy)]
 pub struct BallHandleBundle {
     pub path: PathHandle,
     pub slide: SlideHandle,
     pub rotate: RotateHandle,
 }
 
+/// Orientation a ball has rolled into, and the handles it rolled from.
+pub struct BallRoll {
+    pub rotation: Quat,
+    pub last: BallHandleBundle,
+}
+
+impl BallRoll {
+    pub fn new(handle: BallHandleBundle) -> Self {
+        BallRoll {
+            rotation: Quat::IDENTITY,
+            last: handle,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct PathHandle {
     pub t: WrappingF32,
@@ -126,6 +399,15 @@ impl RotateHandle {
     }
 }
 
+/// Which logical part of the puzzle a `BallSensor` covers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SensorSlot {
+    /// Index into `CubeDescriptor::group_origin_iter`.
+    Group(usize),
+    SlideWindow,
+    RotateWindow,
+}
+
 pub struct BallSensor {
     shape: shape::Box,
     capacity: usize,
@@ -147,6 +429,7 @@ impl BallSensor {
         }
     }
 
+    /// Only used to cross-check `SensorSlot` membership in debug mode.
     pub fn intersect_point(&self, point: Vec3) -> bool {
         let x = point.x >= self.shape.min_x && point.x <= self.shape.max_x;
         let y = point.y >= self.shape.min_y && point.y <= self.shape.max_y;
@@ -171,6 +454,7 @@ pub enum MovementKind {
     Rotate,
 }
 
+#[derive(Clone, Copy)]
 pub struct Movement {
     pub grabbing: GrabbingSensor,
     pub movement: f32,
@@ -180,6 +464,23 @@ pub struct Animation {
     pub kind: MovementKind,
     pub movement: f32,
     pub base: f32,
+    pub ease: Ease,
+}
+
+#[derive(Clone, Copy)]
+pub enum Ease {
+    Linear,
+    /// Constant deceleration, ends with zero speed.
+    Decelerate,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::Decelerate => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -192,6 +493,102 @@ pub struct GrabbingSensor {
 pub struct GrabStatus {
     pub grabbing: Option<GrabbingSensor>,
     pub origin: Vec3,
+    /// Smoothed handle movement per second of the grabbing drag.
+    pub velocity: f32,
+}
+
+pub struct DragConfig {
+    /// Keep a flicked loop spinning after release.
+    pub inertia: bool,
+    /// Path handle units per second squared.
+    pub deceleration: f32,
+    /// Most slots a flick can carry a loop.
+    pub max_carry: usize,
+    /// Scale of the handle movement per pixel dragged.
+    pub sensitivity: f32,
+    /// Length of the snap animation after a slow release.
+    pub snap_seconds: f32,
+}
+
+impl Default for DragConfig {
+    fn default() -> Self {
+        DragConfig {
+            inertia: true,
+            deceleration: 4.0,
+            max_carry: 18,
+            sensitivity: 1.0,
+            snap_seconds: 0.2,
+        }
+    }
+}
+
+pub struct SnapEvent {
+    pub grabbing: GrabbingSensor,
+    pub velocity: f32,
+}
+
+/// A drag or key press was ignored because the sensor was not full.
+pub struct MoveBlockedEvent {
+    pub kind: MovementKind,
+    pub sensor: Entity,
+    pub reason: String,
+}
+
+/// Which player input the puzzle reads: the keyboard for the moves, the
+/// view, style and pause keys, the mouse and touch for drags, orbiting and
+/// zoom. Host apps can drive it with `PuzzleCommand` events in any mode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputMode {
+    KeyboardAndMouse,
+    Keyboard,
+    Mouse,
+    /// No player input, only `PuzzleCommand` events move the puzzle.
+    External,
+}
+
+impl Default for InputMode {
+    fn default() -> Self {
+        InputMode::KeyboardAndMouse
+    }
+}
+
+impl InputMode {
+    pub fn keyboard(&self) -> bool {
+        matches!(self, InputMode::KeyboardAndMouse | InputMode::Keyboard)
+    }
+
+    pub fn mouse(&self) -> bool {
+        matches!(self, InputMode::KeyboardAndMouse | InputMode::Mouse)
+    }
+}
+
+/// Drives the puzzle from outside, like the move and reset keys do.
+#[derive(Clone, Copy, Debug)]
+pub enum PuzzleCommand {
+    /// Ignored, with a `MoveBlockedEvent`, if the move is not possible.
+    Move(crate::puzzle::Move),
+    Undo,
+    /// Seeded scrambles repeat, for shared puzzles.
+    Scramble { seed: Option<u64> },
+    Reset,
 }
 
-pub struct SnapEvent(pub GrabbingSensor);
+/// `PuzzleCommand`s waiting for `AppState::InGame`, taken one per frame.
+#[derive(Default)]
+pub(crate) struct CommandQueue(pub VecDeque<PuzzleCommand>);
+
+/// Short side-to-side shake of a block refusing a move.
+pub struct Shake {
+    pub timer: Timer,
+    /// Applied on top of the rest `translation.x`.
+    pub offset: f32,
+}
+
+impl Default for Shake {
+    fn default() -> Self {
+        Shake {
+            timer: Timer::from_seconds(0.3, false),
+            offset: 0.0,
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use bevy::prelude::*;
 
-use crate::component::{BallHandleBundle, PathHandle, SlideHandle, RotateHandle};
+use crate::component::{BallColor, BallHandleBundle, PathHandle, SlideHandle, RotateHandle, SensorSlot};
 
-use crate::util::{self, bezier::Path};
+use crate::puzzle;
+use crate::util::{self, bezier::{ArcLength, Path}};
 
-const N_BALLS: usize = 18;
-const SLIDE_N_BALLS: usize = 4;
+const SLIDE_N_BALLS: usize = puzzle::WINDOW_LEN;
 const ROTATE_N_BALLS: usize = 2;
 const BALL_RADIANS: f32 = 0.3;
-const BALL_STEP: f32 = 1.0 / N_BALLS as f32;
+const BALL_STEP: f32 = 1.0 / puzzle::LOOP_LEN as f32;
+/// Arc length samples per path segment.
+const ARC_SAMPLES: usize = 64;
 
 const FLOOR_Y: f32 = 0.3;
 
@@ -29,6 +31,7 @@ const GROUP_LEFT_OX: f32 = -1.5;
 const GROUP_OY: f32 = 0.0;
 const GROUP_UP_Z: f32 = FLOOR_Y + BALL_RADIANS;
 
+#[derive(Clone)]
 pub struct CubeDescriptor {
     pub left_path: BallPath,
     pub right_path: BallPath,
@@ -48,6 +51,12 @@ impl Default for CubeDescriptor {
 }
 
 impl CubeDescriptor {
+    /// Replaces both loops with `right` and its mirror.
+    pub fn set_ball_path(&mut self, right: Path<6>) {
+        self.left_path = BallPath::mirror(right.clone());
+        self.right_path = BallPath::new(right);
+    }
+
     pub fn group_origin_iter(&self) -> impl Iterator<Item=(&str, Vec3)> {
         std::array::IntoIter::new([
             ("group.a", Vec3::new( GROUP_LEFT_OX, GROUP_OY,  GROUP_UP_Z)),
@@ -58,7 +67,7 @@ impl CubeDescriptor {
     }
 
     pub fn group_capacity(&self) -> usize {
-        N_BALLS
+        puzzle::LOOP_LEN
     }
 
     pub fn slide_capacity(&self) -> usize {
@@ -69,10 +78,6 @@ impl CubeDescriptor {
         ROTATE_N_BALLS * 2
     }
 
-    pub fn balls_per_color(&self) -> usize {
-        N_BALLS - 2
-    }
-
     pub fn ball_radians(&self) -> f32 {
         BALL_RADIANS
     }
@@ -85,10 +90,10 @@ impl CubeDescriptor {
         const SKIP_N_BALLS: usize = SLIDE_N_BALLS;
         let step = self.ball_step();
         let groups = [
-            (SlideHandle::left() , RotateHandle::up()  , 0..N_BALLS),
-            (SlideHandle::right(), RotateHandle::up()  , SKIP_N_BALLS..N_BALLS),
-            (SlideHandle::left() , RotateHandle::down(), 0..N_BALLS),
-            (SlideHandle::right(), RotateHandle::down(), SKIP_N_BALLS..N_BALLS),
+            (SlideHandle::left() , RotateHandle::up()  , 0..puzzle::LOOP_LEN),
+            (SlideHandle::right(), RotateHandle::up()  , SKIP_N_BALLS..puzzle::LOOP_LEN),
+            (SlideHandle::left() , RotateHandle::down(), 0..puzzle::LOOP_LEN),
+            (SlideHandle::right(), RotateHandle::down(), SKIP_N_BALLS..puzzle::LOOP_LEN),
         ];
         std::array::IntoIter::new(groups)
             .map(move |(slide, rotate, range)| {
@@ -100,6 +105,64 @@ impl CubeDescriptor {
             .flatten()
     }
 
+    /// Handles of a ball resting in slot `index` of loop `group`.
+    pub fn slot_handle(&self, group: usize, index: usize) -> BallHandleBundle {
+        BallHandleBundle {
+            path: PathHandle::new(index as f32 * self.ball_step()),
+            slide: if group % 2 == 0 { SlideHandle::left() } else { SlideHandle::right() },
+            rotate: if group < 2 { RotateHandle::up() } else { RotateHandle::down() },
+        }
+    }
+
+    /// Color of ball number `index`, see `puzzle::home`.
+    pub fn home_color(&self, index: usize) -> BallColor {
+        BallColor::ALL[puzzle::color(index as u8)]
+    }
+
+    pub fn ball_place(&self, path: &PathHandle, slide: &SlideHandle, rotate: &RotateHandle) -> BallPlace {
+        let x = path.t.to_f32() / self.ball_step();
+        let index = if (x - x.round()).abs() < 0.25 {
+            Some(x.round() as usize % puzzle::LOOP_LEN)
+        } else {
+            None
+        };
+        let r = rotate.t.to_f32();
+        BallPlace {
+            is_left: slide.t.to_f32() < 0.5,
+            is_up: r < 0.25 || r > 0.75,
+            index,
+        }
+    }
+
+    /// Whether a ball at `place` belongs in `sensor`, with the blocks on the
+    /// left if `is_block_left`.
+    pub fn sensor_contains(&self, sensor: SensorSlot, place: BallPlace, is_block_left: bool) -> bool {
+        let in_window = |slots: std::ops::Range<usize>| {
+            place.is_left == is_block_left && place.index.map_or(false, |i| slots.contains(&i))
+        };
+        match sensor {
+            SensorSlot::Group(group) => place.group() == group,
+            SensorSlot::SlideWindow => in_window(0..SLIDE_N_BALLS),
+            SensorSlot::RotateWindow => in_window(1..1 + ROTATE_N_BALLS),
+        }
+    }
+
+    /// Slot of a ball at `place` among the `capacity` slots of `sensor`. A
+    /// window lists its up half, then its down half backwards, so a ring of
+    /// them goes around the window.
+    pub fn sensor_index(&self, sensor: SensorSlot, place: BallPlace) -> Option<usize> {
+        let index = place.index?;
+        let in_window = |start: usize, len: usize| {
+            let i = index.checked_sub(start).filter(|i| *i < len)?;
+            Some(if place.is_up { i } else { 2 * len - 1 - i })
+        };
+        match sensor {
+            SensorSlot::Group(_) => Some(index),
+            SensorSlot::SlideWindow => in_window(0, SLIDE_N_BALLS),
+            SensorSlot::RotateWindow => in_window(1, ROTATE_N_BALLS),
+        }
+    }
+
     pub fn get_ball_transform(&self, handle: &BallHandleBundle) -> Transform {
         let (path, outside_v) = if handle.slide.t.to_f32() < 0.5 {
             self.left_path()
@@ -115,6 +178,38 @@ impl CubeDescriptor {
         Transform::from_translation(v)
     }
 
+    /// Rotation of a ball rolling on the cube from the `from` handles to `to`:
+    /// along the loop and across with the slide block, and carried around
+    /// with the rotate block.
+    pub fn ball_roll(&self, from: &BallHandleBundle, to: &BallHandleBundle) -> Quat {
+        fn wrapping_delta(from: f32, to: f32) -> f32 {
+            (to - from + 0.5).rem_euclid(1.0) - 0.5
+        }
+
+        let (path, _) = if to.slide.t.to_f32() < 0.5 {
+            self.left_path()
+        } else {
+            self.right_path()
+        };
+        let rotate_q = self.rotate_path.evaluate(to.rotate);
+        let normal = rotate_q * Vec3::Z;
+        let roll = |direction: Vec3, distance: f32| {
+            let axis = normal.cross(direction);
+            if axis.length_squared() > 0.0 && distance != 0.0 {
+                Quat::from_axis_angle(axis.normalize(), distance / BALL_RADIANS)
+            } else {
+                Quat::IDENTITY
+            }
+        };
+
+        let path_d = wrapping_delta(from.path.t.to_f32(), to.path.t.to_f32()) * path.length();
+        let slide_d = self.slide_path.evaluate(to.slide).x - self.slide_path.evaluate(from.slide).x;
+        let rotate_d = wrapping_delta(from.rotate.t.to_f32(), to.rotate.t.to_f32());
+        let carry = Quat::from_rotation_y(rotate_d * (self.rotate_path.p2 - self.rotate_path.p1));
+
+        carry * roll(Vec3::X, slide_d) * roll(path.tangent(to.path), path_d)
+    }
+
     fn left_path(&self) -> (&BallPath, Vec3) {
         (&self.left_path, Vec3::new(PATH_OUTSIDE_ORIGIN_X, 0.0, 0.0))
     }
@@ -157,28 +252,103 @@ impl CubeDescriptor {
         }
     }
 
+    /// Whole slots a loop released at `velocity` keeps travelling before
+    /// `deceleration` stops it.
+    pub fn path_carry(&self, velocity: f32, deceleration: f32, max_slots: usize) -> f32 {
+        let distance = velocity * velocity / (2.0 * deceleration);
+        let slots = (distance / BALL_STEP).round().min(max_slots as f32);
+        (slots * BALL_STEP).copysign(velocity)
+    }
+
+    /// Solid body under the four loops, the balls sit on its faces.
+    pub fn body_box(&self) -> shape::Box {
+        const MARGIN: f32 = 0.2;
+        let (_, max) = self.right_path.bounds();
+        let hx = -GROUP_LEFT_OX + max.x + BALL_RADIANS + MARGIN;
+        let hy = max.y + BALL_RADIANS + MARGIN;
+        shape::Box::new(hx * 2.0, hy * 2.0, FLOOR_Y * 2.0)
+    }
+
+    /// Slide block, centered on its own origin.
+    pub fn slide_block_box(&self) -> shape::Box {
+        const HZ: f32 = FLOOR_Y + 0.01;
+        let hx = self.block_half_x();
+        let hy = self.window_half_y(0..SLIDE_N_BALLS) + BALL_RADIANS;
+        shape::Box::new(hx * 2.0, hy * 2.0, HZ * 2.0)
+    }
+
+    /// Rotate block, centered on its own origin.
+    pub fn rotate_block_box(&self) -> shape::Box {
+        const HZ: f32 = FLOOR_Y + 0.02;
+        let hx = self.block_half_x();
+        let hy = self.window_half_y(1..1 + ROTATE_N_BALLS) + BALL_RADIANS;
+        shape::Box::new(hx * 2.0, hy * 2.0, HZ * 2.0)
+    }
+
+    pub fn floor_z(&self) -> f32 {
+        FLOOR_Y
+    }
+
     pub fn ball_sensor_box(&self) -> shape::Box {
-        const HX: f32 = -PATH_OUTSIDE_ORIGIN_X + BALL_RADIANS;
-        const HY: f32 = PATH_TOP_ORIGIN_Y + BALL_RADIANS;
         const HZ: f32 = BALL_RADIANS / 2.0 + 0.1;
-        shape::Box::new(HX * 2.0, HY * 2.0, HZ * 2.0)
+        let (_, max) = self.right_path.bounds();
+        let hx = max.x + BALL_RADIANS;
+        let hy = max.y + BALL_RADIANS;
+        shape::Box::new(hx * 2.0, hy * 2.0, HZ * 2.0)
     }
 
     pub fn slide_sensor_box(&self) -> shape::Box {
-        const HX: f32 = -PATH_INSIDE_OX + BALL_RADIANS;
-        const HY: f32 = BALL_RADIANS * SLIDE_N_BALLS as f32;
         const HZ: f32 = GROUP_UP_Z + BALL_RADIANS + 0.05;
-        shape::Box::new(HX * 2.0, HY * 2.0, HZ * 2.0)
+        let hx = self.block_half_x();
+        let hy = self.window_half_y(0..SLIDE_N_BALLS) + BALL_RADIANS;
+        shape::Box::new(hx * 2.0, hy * 2.0, HZ * 2.0)
     }
 
     pub fn rotate_sensor_box(&self) -> shape::Box {
         const HX: f32 = -GROUP_LEFT_OX;
-        const HY: f32 = BALL_RADIANS * ROTATE_N_BALLS as f32;
         const HZ: f32 = GROUP_UP_Z + BALL_RADIANS + 0.1;
-        shape::Box::new(HX * 2.0, HY * 2.0, HZ * 2.0)
+        let hy = self.window_half_y(1..1 + ROTATE_N_BALLS) + BALL_RADIANS;
+        shape::Box::new(HX * 2.0, hy * 2.0, HZ * 2.0)
+    }
+
+    /// Half width of a block, from the window of one loop to the inner side
+    /// of the other.
+    fn block_half_x(&self) -> f32 {
+        let (min, _) = self.right_path.bounds();
+        -GROUP_LEFT_OX + min.x + BALL_RADIANS
+    }
+
+    /// Farthest ball center of the window over `slots` from the middle of
+    /// the blocks.
+    fn window_half_y(&self, slots: std::ops::Range<usize>) -> f32 {
+        slots
+            .map(|i| self.right_path.evaluate(PathHandle::new(i as f32 * BALL_STEP)).y.abs())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Logical place of a ball, derived from its handles alone.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BallPlace {
+    pub is_left: bool,
+    pub is_up: bool,
+    /// Path slot, `None` while between two slots.
+    pub index: Option<usize>,
+}
+
+impl BallPlace {
+    /// Index into `CubeDescriptor::group_origin_iter`.
+    pub fn group(&self) -> usize {
+        match (self.is_left, self.is_up) {
+            ( true,  true) => 0,
+            (false,  true) => 1,
+            ( true, false) => 2,
+            (false, false) => 3,
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct SlicePath {
     p1: f32,
     p2: f32,
@@ -200,6 +370,7 @@ impl SlicePath {
     }
 }
 
+#[derive(Clone)]
 pub struct RotatePath {
     p1: f32,
     p2: f32,
@@ -221,14 +392,33 @@ impl RotatePath {
     }
 }
 
-pub struct BallPath(Path<6>);
+/// Closed loop of the balls, parameterized by arc length so equal handle
+/// steps are equally spaced.
+#[derive(Clone)]
+pub struct BallPath {
+    path: Path<6>,
+    arc: ArcLength,
+}
 
 impl BallPath {
+    fn new(path: Path<6>) -> Self {
+        let arc = path.arc_length(ARC_SAMPLES);
+        BallPath { path, arc }
+    }
+
     fn left() -> Self {
-        let mut p = BallPath::right();
-        p.0.reverse();
-        let [cp6, cp5, cp4, cp3, cp2, cp1] = p.0.cp;
-        BallPath( Path {
+        BallPath::mirror(BallPath::right_curve())
+    }
+
+    fn right() -> Self {
+        BallPath::new(BallPath::right_curve())
+    }
+
+    /// The left loop, `right` run backwards and starting from its `cp5`.
+    fn mirror(mut p: Path<6>) -> Self {
+        p.reverse();
+        let [cp6, cp5, cp4, cp3, cp2, cp1] = p.cp;
+        BallPath::new(Path {
             cp: [
                 cp5,
                 cp4,
@@ -240,7 +430,9 @@ impl BallPath {
         })
     }
 
-    fn right() -> Self {
+    /// Right loop fitted to the model by hand, used when the scene has no
+    /// `path.cp*` nodes.
+    pub fn right_curve() -> Path<6> {
         let cp1 = util::bezier::ControlPoint {
             backward: Vec3::new(  PATH_OUTSIDE_FORWARD_X,  -PATH_SIDE_FORWARD_Y, 0.0),
             origin:   Vec3::new(   PATH_OUTSIDE_ORIGIN_X,   -PATH_SIDE_ORIGIN_Y, 0.0),
@@ -272,7 +464,7 @@ impl BallPath {
             forward:  Vec3::new(     PATH_TOP_BACKWARD_X,  -PATH_TOP_BACKWARD_Y, 0.0),
         };
 
-        BallPath(Path {
+        Path {
             cp: [
                 cp1,
                 cp2,
@@ -281,10 +473,26 @@ impl BallPath {
                 cp5,
                 cp6,
             ]
-        })
+        }
     }
 
     pub fn evaluate(&self, h: PathHandle) -> Vec3 {
-        self.0.evaluate(h.t.to_f32())
+        self.path.evaluate(self.arc.parameter(h.t.to_f32()))
+    }
+
+    pub fn length(&self) -> f32 {
+        self.arc.length()
+    }
+
+    /// Corners of the box around the loop, in its plane.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let samples = ARC_SAMPLES * Path::<6>::N_POINTS;
+        (0..samples)
+            .map(|i| self.evaluate(PathHandle::new(i as f32 / samples as f32)).truncate())
+            .fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), p| (min.min(p), max.max(p)))
+    }
+
+    pub fn tangent(&self, h: PathHandle) -> Vec3 {
+        self.path.tangent(self.arc.parameter(h.t.to_f32()))
     }
 }
@@ -2,16 +2,29 @@ use bevy::prelude::*;
 use bevy::core::FloatOrd;
 use bevy_mod_picking::PickableBundle;
 
-use crate::AppState;
+use crate::{AppState, not_paused};
 use crate::component::*;
-use debug::DebugVisible;
+use crate::puzzle::History;
+use debug::{Debug, DebugVisible};
 use crate::input;
 
 use crate::util::otry;
 
+mod highlight;
+pub(crate) mod history;
+#[cfg(debug_assertions)]
+mod invariant;
+mod net;
+mod solved;
+mod style;
+mod xray;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, SystemLabel)]
-enum Stage {
+pub(crate) enum Stage {
     SyncTransform,
+    Trace,
+    /// Systems starting or ending `AppState::Animating`.
+    Snap,
 }
 
 pub struct CubePlugin;
@@ -21,19 +34,45 @@ impl Plugin for CubePlugin {
         app
             .init_resource::<CubeDescriptor>()
             .init_resource::<GrabStatus>()
+            .init_resource::<ViewConfig>()
+            .init_resource::<DragConfig>()
+            .init_resource::<BallStyle>()
+            .init_resource::<net::NetView>()
+            .init_resource::<XRayConfig>()
+            .init_resource::<SolveMode>()
+            .init_resource::<History>()
+            .init_resource::<InputMode>()
+            .init_resource::<CommandQueue>()
             .add_event::<SnapEvent>()
+            .add_event::<MoveBlockedEvent>()
+            .add_event::<SolvedEvent>()
+            .add_event::<RestoredEvent>()
+            .add_event::<PuzzleCommand>()
+            .add_startup_system(net::setup_net.system())
+            // in every state, events left unread out of the game would expire
+            .add_system_to_stage(CoreStage::PreUpdate, input::queue_commands.system())
             .add_system_set(SystemSet::on_enter(AppState::Setup)
                 .with_system(setup_ball.system())
                 .with_system(setup_sensor.system())
                 .with_system(setup_cube.system())
             )
-            .add_system_set(SystemSet::on_update(AppState::Pause)
+            .add_system_set(SystemSet::on_update(AppState::Animating)
                 .before(Stage::SyncTransform)
-                .with_system(animation.system())
+                .with_system(animation.system().label(Stage::Snap))
             )
             .add_system_set(SystemSet::new()
                 .before(Stage::SyncTransform)
+                .with_run_criteria(not_paused.system())
                 .with_system(input::grab.system().chain(input::cube_rotate.system()))
+                .with_system(view_animation.system())
+                .with_system(input::zoom.system())
+                .with_system(shake_blocked.system())
+                .with_system(shake.system())
+                .with_system(input::ball_style.system())
+                .with_system(style::apply_ball_style.system())
+                .with_system(style::apply_ball_labels.system())
+                .with_system(input::solve_mode.system())
+                .with_system(xray::toggle_xray.system())
             )
             .add_system_set(SystemSet::on_enter(AppState::InGame)
                 .with_system(cube_transform.system())
@@ -45,7 +84,15 @@ impl Plugin for CubePlugin {
                 .before(Stage::SyncTransform)
                 .with_system(input::drag.system().chain(input::apply_movement.system()))
                 .with_system(input::key.system().chain(input::apply_movement.system()))
-                .with_system(input::reset.system())
+                .with_system(net::click_net.system().chain(input::apply_movement.system()))
+                .with_system(input::next_command.system()
+                    .chain(history::restore.system())
+                    .chain(input::command.system())
+                    .chain(input::apply_movement.system())
+                )
+                .with_system(history::restore_key.system())
+                .with_system(input::view.system())
+                .with_system(input::free_orbit.system())
             )
             .add_system_set(SystemSet::new()
                 .label(Stage::SyncTransform)
@@ -56,11 +103,30 @@ impl Plugin for CubePlugin {
             )
             .add_system_set(SystemSet::new()
                 .after(Stage::SyncTransform)
-                .with_system(trace_ball.system())
+                .with_system(trace_ball.system().label(Stage::Trace))
+                .with_system(highlight::highlight.system())
+                .with_system(net::toggle_net.system())
+                .with_system(net::refresh_net.system())
+                .with_system(xray::xray.system())
+                .with_system(xray::xray_opacity.system())
             )
             .add_system_set(SystemSet::on_update(AppState::InGame)
                 .after(Stage::SyncTransform)
-                .with_system(snap.system())
+                .with_system(snap.system().label(Stage::Snap))
+                .with_system(check_sensor_box.system())
+                .with_system(solved::check_solved.system())
+            )
+            .add_system_set(SystemSet::on_update(AppState::InGame)
+                .after(Stage::Trace)
+                .with_system(history::record_history.system())
+            );
+
+        #[cfg(debug_assertions)]
+        app
+            .init_resource::<invariant::InvariantConfig>()
+            .add_system_set(SystemSet::on_update(AppState::InGame)
+                .after(Stage::Trace)
+                .with_system(invariant::check_invariants.system())
             );
     }
 }
@@ -75,25 +141,29 @@ fn setup_cube(
     )>,
     mesh_query: Query<&Handle<Mesh>>,
 ) {
+    // the scene is validated before `AppState::Setup`, skip anything odd
     let get_first_mesh_child = |children: &Children| {
-        children.iter().find(|c| mesh_query.get(**c).is_ok()).unwrap().clone()
+        children.iter().find(|c| mesh_query.get(**c).is_ok()).cloned()
     };
 
-    let (entity, children) = query.q0().single().unwrap();
+    let (entity, children) = otry!(query.q0().single().ok());
     commands.entity(entity)
-        .insert(CubeRotation::new(0.5, -0.05));
-    commands.entity(get_first_mesh_child(children))
-        .insert(DebugVisible::No)
-        .insert_bundle(PickableBundle::default()); // no grab kind
+        .insert(CubeRotation::front());
+    if let Some(mesh) = get_first_mesh_child(children) {
+        commands.entity(mesh)
+            .insert(CubeBody)
+            .insert(DebugVisible::No)
+            .insert_bundle(PickableBundle::default()); // no grab kind
+    }
 
     for (entity, name, children, block) in query.q1().iter() {
-        let (grab_kind, sensor) = match block {
+        let (grab_kind, slot, sensor) = match block {
             Block::Slide => {
                 let shape = cube.slide_sensor_box();
                 let capacity = cube.slide_capacity();
                 let sensor = BallSensor::new(shape, capacity);
                 commands.entity(entity).insert(SlideHandle::left());
-                (MovementKind::Slide, sensor)
+                (MovementKind::Slide, SensorSlot::SlideWindow, sensor)
             }
             Block::Rotate => {
                 let shape = cube.rotate_sensor_box();
@@ -101,83 +171,71 @@ fn setup_cube(
                 let sensor = BallSensor::new(shape, capacity);
                 commands.entity(entity)
                     .insert_bundle((SlideHandle::left(), RotateHandle::up()));
-                (MovementKind::Rotate, sensor)
+                (MovementKind::Rotate, SensorSlot::RotateWindow, sensor)
             }
         };
-        commands.entity(get_first_mesh_child(children))
+        let mesh = match get_first_mesh_child(children) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        commands.entity(mesh)
             .insert(name.clone())
             .insert(sensor)
+            .insert(slot)
             .insert(grab_kind)
             .insert(DebugVisible::No)
             .insert_bundle(PickableBundle::default());
     }
 
     for children in query.q2().iter() {
-        commands.entity(get_first_mesh_child(children))
-            .insert(DebugVisible::No);
+        if let Some(mesh) = get_first_mesh_child(children) {
+            commands.entity(mesh)
+                .insert(DebugVisible::No);
+        }
     }
 }
 
 fn setup_ball(
     mut commands: Commands,
     cube: Res<CubeDescriptor>,
+    ball_style: Res<BallStyle>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
     cube_query: Query<Entity, With<Cube>>,
 ) {
     let mesh = meshes.add(Mesh::from(shape::Icosphere { radius: cube.ball_radians(), subdivisions: 12 }));
-    let get_material = {
-        let mut add_material = |base_color| {
-            materials.add(StandardMaterial {
-                base_color,
-                roughness: 0.3,
-                reflectance: 0.2,
-                ..Default::default()
-            })
-        };
-        let a = add_material(Color::rgb(0.6, 0.2, 0.1));
-        let b = add_material(Color::rgb(0.2, 0.6, 0.1));
-        let c = add_material(Color::rgb(0.1, 0.2, 0.6));
-        let d = add_material(Color::rgb(0.6, 0.2, 0.6));
-        use BallColor::*;
-        move |ball| {
-            match ball {
-                A => a.clone(),
-                B => b.clone(),
-                C => c.clone(),
-                D => d.clone(),
-            }
-        }
-    };
-
-    let color_iter = {
-        use std::iter::repeat;
+    let symbols = style::ball_symbols(&mut textures);
+    let ball_materials = style::ball_materials(&ball_style, &symbols, &mut materials);
 
-        let mut colors = repeat(BallColor::A).take(cube.balls_per_color())
-            .chain(repeat(BallColor::B).take(cube.balls_per_color()))
-            .chain(repeat(BallColor::C).take(cube.balls_per_color()))
-            .chain(repeat(BallColor::D).take(cube.balls_per_color()))
-            .collect::<Vec<_>>();
-        fastrand::shuffle(&mut colors);
-        colors.into_iter()
-    };
+    let puzzle = history::scrambled(None);
+    let mut handles = vec![None; cube.ball_init_handle_iter().count()];
+    for (ball, group, index) in puzzle.places() {
+        handles[ball as usize] = Some(cube.slot_handle(group, index));
+    }
 
     let cube_entity = cube_query.single().unwrap();
     commands.entity(cube_entity).with_children(|parent| {
-        for (handle, color) in cube.ball_init_handle_iter().zip(color_iter) {
-            let material = get_material(color);
-
+        for (index, handle) in handles.into_iter().enumerate() {
+            let handle = handle.unwrap();
+            let color = cube.home_color(index);
             let pbr = PbrBundle {
                 mesh: mesh.clone(),
-                material: material.clone(),
+                material: ball_materials.normal[color.index()].clone(),
                 ..Default::default()
             };
 
             parent.spawn_bundle(pbr)
                 .insert(color)
+                .insert(BallIndex(index))
+                .insert(BallRoll::new(handle))
                 .insert_bundle(handle);
         }
     });
+
+    commands.insert_resource(ball_materials);
+    commands.insert_resource(symbols);
+    commands.insert_resource(History::new(puzzle));
 }
 
 fn setup_sensor(
@@ -224,46 +282,115 @@ fn setup_sensor(
         //let material = materials.add(Color::rgba(0.0, 0.7, 0.5, 0.4).into());
         let material = materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0).into());
 
-        for (name, origin) in cube.group_origin_iter() {
+        for (group, (name, origin)) in cube.group_origin_iter().enumerate() {
             let sensor = sensor_bundle(cube.group_capacity(), origin, shape, mesh.clone(), material.clone());
 
             parent.spawn_bundle(sensor)
                 .insert(Name::new(name.to_string()))
+                .insert(SensorSlot::Group(group))
                 .insert(MovementKind::Path)
                 .insert_bundle(PickableBundle::default());
         }
     });
 }
 
+fn view_animation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ViewAnimation)>,
+) {
+    for (entity, mut animation) in query.iter_mut() {
+        if animation.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<ViewAnimation>();
+        }
+    }
+}
+
+type ViewChanged = Or<(Changed<CubeRotation>, Changed<ViewAnimation>)>;
 fn cube_transform(
-    mut query: Query<(&mut Transform, &CubeRotation), Changed<CubeRotation>>,
+    mut query: Query<(&mut Transform, &CubeRotation, Option<&ViewAnimation>), ViewChanged>,
+) {
+    let (mut transform, rotation, animation) = otry!(query.single_mut().ok());
+    let to = rotation.to_quat();
+    transform.rotation = match animation {
+        Some(animation) => animation.from.slerp(to, animation.timer.percent()),
+        None => to,
+    };
+}
+
+fn shake_blocked(
+    mut commands: Commands,
+    mut events: EventReader<MoveBlockedEvent>,
+    mut query: Query<(Entity, &Block, Option<&mut Shake>)>,
 ) {
-    let (mut transform, rotation) = otry!(query.single_mut().ok());
-    transform.rotation = rotation.to_quat();
+    for ev in events.iter() {
+        for (entity, block, shake) in query.iter_mut() {
+            let is_target = match block {
+                Block::Slide => !matches!(ev.kind, MovementKind::Rotate),
+                Block::Rotate => matches!(ev.kind, MovementKind::Rotate),
+            };
+            if !is_target {
+                continue;
+            }
+
+            match shake {
+                // keep the applied offset so it can be taken back
+                Some(mut shake) => shake.timer.reset(),
+                None => {
+                    commands.entity(entity).insert(Shake::default());
+                }
+            }
+        }
+    }
+}
+
+fn shake(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Shake)>,
+) {
+    const AMPLITUDE: f32 = 0.06;
+    const SWINGS: f32 = 3.0;
+    for (entity, mut transform, mut shake) in query.iter_mut() {
+        let t = shake.timer.tick(time.delta()).percent();
+        let offset = if shake.timer.finished() {
+            commands.entity(entity).remove::<Shake>();
+            0.0
+        } else {
+            let angle = t * std::f32::consts::PI * 2.0 * SWINGS;
+            angle.sin() * AMPLITUDE * (1.0 - t)
+        };
+        transform.translation.x += offset - shake.offset;
+        shake.offset = offset;
+    }
 }
 
 type BallChanged = Or<(Changed<PathHandle>, Changed<SlideHandle>, Changed<RotateHandle>)>;
 fn ball_transform(
     cube: Res<CubeDescriptor>,
-    mut query: Query<(&mut Transform, &PathHandle, &SlideHandle, &RotateHandle), BallChanged>,
+    mut query: Query<(&mut Transform, &mut BallRoll, &PathHandle, &SlideHandle, &RotateHandle), BallChanged>,
 ) {
-    for (mut transform, path_h, slide_h, rotate_h) in query.iter_mut() {
+    for (mut transform, mut roll, path_h, slide_h, rotate_h) in query.iter_mut() {
         let bundle = BallHandleBundle {
             path: *path_h,
             slide: *slide_h,
             rotate: *rotate_h,
         };
+        roll.rotation = (cube.ball_roll(&roll.last, &bundle) * roll.rotation).normalize();
+        roll.last = bundle;
         *transform = cube.get_ball_transform(&bundle);
+        transform.rotation = roll.rotation;
     }
 }
 
 fn slide_block_transform(
     cube: Res<CubeDescriptor>,
-    mut query: Query<(&mut Transform, &SlideHandle), (Changed<SlideHandle>, With<Block>)>,
+    mut query: Query<(&mut Transform, &SlideHandle, Option<&Shake>), (Changed<SlideHandle>, With<Block>)>,
 ) {
-    for (mut transform, handle) in query.iter_mut() {
+    for (mut transform, handle, shake) in query.iter_mut() {
         let v = cube.slide_path.evaluate(*handle);
-        transform.translation.x = v.x;
+        // keep the offset `shake` takes back later
+        transform.translation.x = v.x + shake.map_or(0.0, |shake| shake.offset);
     }
 }
 
@@ -277,26 +404,78 @@ fn rotate_block_transform(
     }
 }
 
+type HandleChanged = Or<(Changed<PathHandle>, Changed<SlideHandle>, Changed<RotateHandle>)>;
+
+/// Fills every `BallSensor` from the ball handles, only when some handle moved.
 fn trace_ball(
-    mut sensor_query: Query<(&Transform, &mut BallSensor)>,
-    ball_query: Query<(Entity, &Transform, &BallColor, &PathHandle)>,
+    cube: Res<CubeDescriptor>,
+    mut sensor_query: Query<(&SensorSlot, &mut BallSensor)>,
+    block_query: Query<&SlideHandle, With<Block>>,
+    ball_query: Query<(Entity, &BallColor, &PathHandle, &SlideHandle, &RotateHandle)>,
+    changed_query: Query<(), HandleChanged>,
+    added_query: Query<(), Added<BallSensor>>,
 ) {
-    for (transform, mut sensor) in sensor_query.iter_mut() {
-        let sensor_origin = transform.translation;
+    if changed_query.iter().next().is_none() && added_query.iter().next().is_none() {
+        return;
+    }
+    let is_block_left = otry!(block_query.iter().next()).t.to_f32() < 0.5;
+
+    let places = ball_query.iter()
+        .map(|(entity, color, path, slide, rotate)| {
+            (entity, *color, path.t.to_f32(), cube.ball_place(path, slide, rotate))
+        })
+        .collect::<Vec<_>>();
+    for (slot, mut sensor) in sensor_query.iter_mut() {
         sensor.detail.clear();
-        for (entity, transform, color, handle) in ball_query.iter() {
-            let translation = transform.translation - sensor_origin;
-            if sensor.intersect_point(translation) {
-                sensor.detail.push((entity, *color, FloatOrd(handle.t.to_f32())));
+        for (entity, color, t, place) in places.iter() {
+            if cube.sensor_contains(*slot, *place, is_block_left) {
+                sensor.detail.push((*entity, *color, FloatOrd(*t)));
             }
         }
         sensor.detail.sort_by_key(|(_, _, t)| *t);
     }
 }
 
+/// Warns when the sensor boxes disagree with the slots, in debug mode at rest.
+fn check_sensor_box(
+    debug: Option<Res<Debug>>,
+    grab_status: Res<GrabStatus>,
+    mut snap_events: EventReader<SnapEvent>,
+    sensor_query: Query<(&Name, &GlobalTransform, &BallSensor)>,
+    ball_query: Query<(Entity, &GlobalTransform), With<BallColor>>,
+    changed_query: Query<(), HandleChanged>,
+) {
+    if !matches!(debug.as_deref(), Some(Debug::On)) {
+        return;
+    }
+    // a released drag still has to snap
+    if grab_status.grabbing.is_some() || snap_events.iter().next().is_some() {
+        return;
+    }
+    // global transforms follow moved handles a frame later
+    if changed_query.iter().next().is_some() {
+        return;
+    }
+    for (name, transform, sensor) in sensor_query.iter() {
+        // block sensors are meshes under the block, compare in the sensor space
+        let to_sensor = transform.compute_matrix().inverse();
+        let mut boxed = ball_query.iter()
+            .filter(|(_, ball)| sensor.intersect_point(to_sensor.transform_point3(ball.translation)))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        let mut traced = sensor.entities().collect::<Vec<_>>();
+        boxed.sort();
+        traced.sort();
+        if boxed != traced {
+            warn!("{}: box holds {} balls, slots hold {}", name, boxed.len(), traced.len());
+        }
+    }
+}
+
 fn snap(
     mut commands: Commands,
     cube: Res<CubeDescriptor>,
+    config: Res<DragConfig>,
     mut state: ResMut<State<AppState>>,
     mut events: EventReader<SnapEvent>,
     block_query: QuerySet<(
@@ -308,11 +487,21 @@ fn snap(
     slide_query: Query<&SlideHandle>,
     rotate_query: Query<&RotateHandle>,
 ) {
-    fn new_animation_bundle(kind: MovementKind, to: f32, from: f32) -> (Animation, Timer) {
+    fn new_animation_bundle(kind: MovementKind, to: f32, from: f32, seconds: f32) -> (Animation, Timer) {
+        new_eased_animation_bundle(kind, to, from, Ease::Linear, seconds)
+    }
+
+    fn new_eased_animation_bundle(
+        kind: MovementKind,
+        to: f32,
+        from: f32,
+        ease: Ease,
+        seconds: f32,
+    ) -> (Animation, Timer) {
         let offset = to - from;
         (
-            Animation { kind, movement: offset, base: from },
-            Timer::from_seconds(0.2, false),
+            Animation { kind, movement: offset, base: from, ease },
+            Timer::from_seconds(seconds, false),
         )
     }
 
@@ -327,7 +516,7 @@ fn snap(
         }
     }
 
-    let SnapEvent(grabbing) = *otry!(events.iter().next());
+    let SnapEvent { grabbing, velocity } = *otry!(events.iter().next());
     let kind = grabbing.kind;
     let sensor = sensor_query.get(grabbing.entity).unwrap();
     if !sensor.is_full() {
@@ -340,14 +529,27 @@ fn snap(
             let first = iter.next().unwrap();
             let handle = path_query.get(first).unwrap().t.to_f32();
 
-            let mut to = cube.path_snap_first(handle);
-            let animation = new_animation_bundle(kind, to, handle);
+            let carry = if config.inertia {
+                cube.path_carry(velocity, config.deceleration, config.max_carry)
+            } else {
+                0.0
+            };
+            let mut to = cube.path_snap_first(handle) + carry;
+
+            // a flick decelerates from the release velocity down to the slot
+            let (ease, seconds) = if carry != 0.0 {
+                let seconds = 2.0 * (to - handle).abs() / velocity.abs();
+                (Ease::Decelerate, seconds.clamp(config.snap_seconds, 2.0))
+            } else {
+                (Ease::Linear, config.snap_seconds)
+            };
+            let animation = new_eased_animation_bundle(kind, to, handle, ease, seconds);
             commands.entity(first).insert_bundle(animation);
 
             for entity in iter {
                 let handle = path_query.get(entity).unwrap().t.to_f32();
                 to += cube.ball_step();
-                let animation = new_animation_bundle(kind, to, handle);
+                let animation = new_eased_animation_bundle(kind, to, handle, ease, seconds);
                 commands.entity(entity).insert_bundle(animation);
             }
         }
@@ -355,7 +557,7 @@ fn snap(
             for entity in block_query.q0().iter().chain(sensor.entities()) {
                 let handle = slide_query.get(entity).unwrap().t.to_f32();
                 let to = snap3(handle);
-                let animation = new_animation_bundle(kind, to, handle);
+                let animation = new_animation_bundle(kind, to, handle, config.snap_seconds);
                 commands.entity(entity).insert_bundle(animation);
             }
         }
@@ -363,13 +565,13 @@ fn snap(
             for entity in block_query.q1().iter().chain(sensor.entities()) {
                 let handle = rotate_query.get(entity).unwrap().t.to_f32();
                 let to = snap3(handle);
-                let animation = new_animation_bundle(kind, to, handle);
+                let animation = new_animation_bundle(kind, to, handle, config.snap_seconds);
                 commands.entity(entity).insert_bundle(animation);
             }
         }
     }
 
-    state.set(AppState::Pause).unwrap();
+    state.set(AppState::Animating).unwrap();
 }
 
 fn animation(
@@ -385,7 +587,7 @@ fn animation(
     let mut i = 0;
     for (entity, path_h, slide_h, rotate_h, animation, mut timer) in query.iter_mut() {
         i += 1;
-        let t = timer.tick(time.delta()).percent();
+        let t = animation.ease.apply(timer.tick(time.delta()).percent());
         match animation.kind {
             MovementKind::Path => {
                 let mut handle = path_h.unwrap();
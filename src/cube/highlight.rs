@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy_mod_picking::PickingCamera;
+
+use crate::component::*;
+
+use crate::util::otry;
+
+/// Lights up the balls a drag would move: the hovered sensor while idle, the
+/// grabbed one while dragging.
+pub(super) fn highlight(
+    grab_status: Res<GrabStatus>,
+    ball_materials: Option<Res<BallMaterials>>,
+    picking_query: Query<&PickingCamera>,
+    sensor_query: Query<&BallSensor>,
+    mut ball_query: Query<(Entity, &BallColor, &mut Handle<StandardMaterial>)>,
+) {
+    let ball_materials = otry!(ball_materials);
+
+    let (target, lit) = match grab_status.grabbing {
+        Some(grabbing) => (Some(grabbing.entity), &ball_materials.grab),
+        None => {
+            let hover = picking_query.single().ok()
+                .and_then(|picking| picking.intersect_top())
+                .map(|(entity, _)| entity);
+            (hover, &ball_materials.hover)
+        }
+    };
+    let sensor = target
+        .and_then(|entity| sensor_query.get(entity).ok())
+        .filter(|sensor| sensor.is_full());
+
+    for (entity, color, mut material) in ball_query.iter_mut() {
+        let is_lit = sensor.map_or(false, |sensor| sensor.entities().any(|e| e == entity));
+        let wanted = if is_lit {
+            &lit[color.index()]
+        } else {
+            &ball_materials.normal[color.index()]
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
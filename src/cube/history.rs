@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use crate::component::*;
+use crate::puzzle::{History, Move, Puzzle, N_BALLS, SCRAMBLE_MOVES};
+use super::HandleChanged;
+
+/// The puzzle the balls rest in, `None` while any ball is between slots.
+pub(crate) fn puzzle_of<'a>(
+    cube: &CubeDescriptor,
+    balls: impl Iterator<Item=(&'a BallIndex, &'a PathHandle, &'a SlideHandle, &'a RotateHandle)>,
+) -> Option<Puzzle> {
+    let places = balls
+        .map(|(ball, path, slide, rotate)| {
+            let place = cube.ball_place(path, slide, rotate);
+            Some((ball.0 as u8, place.group(), place.index?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Puzzle::from_places(places)
+}
+
+/// A freshly scrambled puzzle, the same one for the same `seed`.
+pub(super) fn scrambled(seed: Option<u64>) -> Puzzle {
+    let rng = seed.map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed);
+    let mut puzzle = Puzzle::solved();
+    puzzle.scramble(&rng, SCRAMBLE_MOVES);
+    puzzle
+}
+
+/// Records every state the balls come to rest in.
+pub(super) fn record_history(
+    cube: Res<CubeDescriptor>,
+    grab_status: Res<GrabStatus>,
+    mut history: ResMut<History>,
+    mut snap_events: EventReader<SnapEvent>,
+    ball_query: Query<(&BallIndex, &PathHandle, &SlideHandle, &RotateHandle)>,
+    changed_query: Query<(), (With<BallIndex>, HandleChanged)>,
+) {
+    // a released drag still has to snap
+    if grab_status.grabbing.is_some() || snap_events.iter().next().is_some() {
+        return;
+    }
+    if changed_query.iter().next().is_none() {
+        return;
+    }
+    if let Some(puzzle) = puzzle_of(&cube, ball_query.iter()) {
+        history.push(puzzle);
+    }
+}
+
+/// Backspace steps back, P scrambles and L resets to solved, queued like
+/// the matching `PuzzleCommand`s.
+pub(super) fn restore_key(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    if !input_mode.keyboard() {
+        return;
+    }
+    let keys = [
+        (KeyCode::Back, PuzzleCommand::Undo),
+        (KeyCode::P, PuzzleCommand::Scramble { seed: None }),
+        (KeyCode::L, PuzzleCommand::Reset),
+    ];
+    let pressed = keys.iter()
+        .filter(|(key_code, _)| key.just_pressed(*key_code))
+        .map(|(_, command)| *command);
+    queue.0.extend(pressed);
+}
+
+/// Carries out an undo, scramble or reset command, and passes moves on to
+/// `input::command`.
+pub(super) fn restore(
+    In(command): In<Option<PuzzleCommand>>,
+    cube: Res<CubeDescriptor>,
+    mut history: ResMut<History>,
+    mut restored_events: EventWriter<RestoredEvent>,
+    mut query: QuerySet<(
+        Query<(&BallIndex, &mut PathHandle, &mut SlideHandle, &mut RotateHandle)>,
+        Query<&mut SlideHandle, With<Block>>,
+        Query<&mut RotateHandle, With<Block>>,
+    )>,
+) -> Option<Move> {
+    let puzzle = match command? {
+        PuzzleCommand::Move(m) => return Some(m),
+        PuzzleCommand::Undo => history.undo()?.clone(),
+        PuzzleCommand::Scramble { seed } => {
+            let puzzle = scrambled(seed);
+            *history = History::new(puzzle.clone());
+            puzzle
+        }
+        PuzzleCommand::Reset => {
+            *history = History::default();
+            Puzzle::solved()
+        }
+    };
+
+    let mut slots = vec![None; N_BALLS];
+    for (ball, group, index) in puzzle.places() {
+        slots[ball as usize] = Some(cube.slot_handle(group, index));
+    }
+    for (ball, mut path, mut slide, mut rotate) in query.q0_mut().iter_mut() {
+        let handle = slots[ball.0]?;
+        *path = handle.path;
+        *slide = handle.slide;
+        *rotate = handle.rotate;
+    }
+
+    for mut handle in query.q1_mut().iter_mut() {
+        *handle = if puzzle.is_block_left() { SlideHandle::left() } else { SlideHandle::right() };
+    }
+    for mut handle in query.q2_mut().iter_mut() {
+        *handle = RotateHandle::up();
+    }
+    restored_events.send(RestoredEvent);
+    None
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use crate::component::*;
+use crate::puzzle::BALLS_PER_COLOR;
+use super::HandleChanged;
+
+/// Tolerance, in slots, of a ball counted as sitting on its slot.
+const SLOT_EPSILON: f32 = 1e-3;
+
+pub struct InvariantConfig {
+    /// Round stray handles back onto their nearest slot.
+    pub repair: bool,
+}
+
+impl Default for InvariantConfig {
+    fn default() -> Self {
+        InvariantConfig {
+            repair: false,
+        }
+    }
+}
+
+fn off_slot(t: f32, step: f32) -> f32 {
+    let x = t / step;
+    (x - x.round()).abs()
+}
+
+/// Verifies the puzzle after every settled move: loop and window counts,
+/// balls sitting on evenly spaced slots, and the balls per color.
+pub(super) fn check_invariants(
+    cube: Res<CubeDescriptor>,
+    config: Res<InvariantConfig>,
+    grab_status: Res<GrabStatus>,
+    mut dirty: Local<bool>,
+    mut snap_events: EventReader<SnapEvent>,
+    sensor_query: Query<(&Name, &SensorSlot, &BallSensor)>,
+    block_query: Query<&SlideHandle, With<Block>>,
+    mut ball_query: QuerySet<(
+        Query<(), HandleChanged>,
+        Query<(&BallColor, &mut PathHandle, &mut SlideHandle, &mut RotateHandle), Without<Block>>,
+    )>,
+) {
+    if ball_query.q0().iter().next().is_some() {
+        *dirty = true;
+    }
+    // not settled yet, a snap starts this frame
+    if !*dirty || grab_status.grabbing.is_some() || snap_events.iter().next().is_some() {
+        return;
+    }
+    *dirty = false;
+
+    let mut violations = Vec::new();
+    let step = cube.ball_step();
+    let is_block_left = match block_query.iter().next() {
+        Some(handle) => handle.t.to_f32() < 0.5,
+        None => return,
+    };
+
+    for (name, slot, sensor) in sensor_query.iter() {
+        let expected = match slot {
+            SensorSlot::Group(group) => {
+                let is_left = *group % 2 == 0;
+                if is_left == is_block_left {
+                    cube.group_capacity()
+                } else {
+                    cube.group_capacity() - cube.slide_capacity() / 2
+                }
+            }
+            SensorSlot::SlideWindow => cube.slide_capacity(),
+            SensorSlot::RotateWindow => cube.rotate_capacity(),
+        };
+        if sensor.detail.len() != expected {
+            violations.push(format!("{} holds {} balls, expected {}", name, sensor.detail.len(), expected));
+        }
+    }
+
+    let mut colors = [0; 4];
+    let mut taken = std::collections::HashSet::new();
+    // read only, `iter_mut` does not flag the handles as changed
+    for (color, path, slide, rotate) in ball_query.q1_mut().iter_mut() {
+        colors[color.index()] += 1;
+
+        let place = cube.ball_place(&path, &slide, &rotate);
+        let is_stray = off_slot(path.t.to_f32(), step) > SLOT_EPSILON
+            || off_slot(slide.t.to_f32(), 1.0) > SLOT_EPSILON
+            || off_slot(rotate.t.to_f32(), 0.5) > SLOT_EPSILON;
+        if is_stray {
+            violations.push(format!(
+                "{} ball between slots at path {:.4}, slide {:.4}, rotate {:.4}",
+                color.to_char(), path.t.to_f32(), slide.t.to_f32(), rotate.t.to_f32(),
+            ));
+        }
+        if !taken.insert((place.group(), place.index)) {
+            violations.push(format!("two balls share slot {:?} of group {}", place.index, place.group()));
+        }
+    }
+    for (color, count) in BallColor::ALL.iter().zip(colors.iter()) {
+        if *count != BALLS_PER_COLOR {
+            violations.push(format!("{} {} balls, expected {}", count, color.to_char(), BALLS_PER_COLOR));
+        }
+    }
+
+    if violations.is_empty() {
+        return;
+    }
+    for violation in violations.iter() {
+        error!("invariant: {}", violation);
+    }
+
+    if config.repair {
+        warn!("invariant: rounding every handle onto its slot");
+        for (_, mut path, mut slide, mut rotate) in ball_query.q1_mut().iter_mut() {
+            let t = (path.t.to_f32() / step).round() * step;
+            *path = PathHandle::new(t);
+            *slide = SlideHandle::new(slide.t.to_f32().round());
+            *rotate = RotateHandle::new((rotate.t.to_f32() * 2.0).round() / 2.0);
+        }
+    }
+}
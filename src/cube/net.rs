@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::component::*;
+use crate::input;
+
+const RING_SIZE: f32 = 96.0;
+const DOT_SIZE: f32 = 10.0;
+const EMPTY: Color = Color::rgb(0.25, 0.25, 0.25);
+
+/// Unfolded 2D view of every loop and window, toggled with M.
+#[derive(Default)]
+pub struct NetView {
+    pub visible: bool,
+}
+
+struct NetPanel;
+
+/// Shows the ball in slot `index` of the `BallSensor` called `sensor`, see
+/// `CubeDescriptor::sensor_index`.
+struct NetDot {
+    sensor: &'static str,
+    index: usize,
+}
+
+struct NetButton {
+    sensor: &'static str,
+    kind: MovementKind,
+    direction: f32,
+}
+
+/// Front loops and the slide window on top, back loops and the rotate window below.
+const LAYOUT: [[(&str, MovementKind); 3]; 2] = [
+    [("group.a", MovementKind::Path), ("block.slide", MovementKind::Slide), ("group.b", MovementKind::Path)],
+    [("group.c", MovementKind::Path), ("block.rotate", MovementKind::Rotate), ("group.d", MovementKind::Path)],
+];
+
+pub(super) fn setup_net(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cube: Res<CubeDescriptor>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("FiraMono-Medium.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+    let transparent = materials.add(Color::NONE.into());
+    let button = materials.add(Color::rgb(0.2, 0.2, 0.2).into());
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                ..Default::default()
+            },
+            flex_direction: FlexDirection::ColumnReverse,
+            display: Display::None,
+            ..Default::default()
+        },
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.5).into()),
+        ..Default::default()
+    })
+    .insert(NetPanel)
+    .with_children(|parent| {
+        for row in LAYOUT.iter() {
+            parent.spawn_bundle(NodeBundle {
+                material: transparent.clone(),
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                for &(sensor, kind) in row.iter() {
+                    let capacity = match kind {
+                        MovementKind::Path => cube.group_capacity(),
+                        MovementKind::Slide => cube.slide_capacity(),
+                        MovementKind::Rotate => cube.rotate_capacity(),
+                    };
+                    parent.spawn_bundle(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            margin: Rect::all(Val::Px(4.0)),
+                            ..Default::default()
+                        },
+                        material: transparent.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        spawn_ring(parent, &mut materials, sensor, capacity, transparent.clone());
+                        spawn_controls(parent, sensor, kind, &text_style, button.clone(), transparent.clone());
+                    });
+                }
+            });
+        }
+    });
+}
+
+fn spawn_ring(
+    parent: &mut ChildBuilder,
+    materials: &mut Assets<ColorMaterial>,
+    sensor: &'static str,
+    capacity: usize,
+    transparent: Handle<ColorMaterial>,
+) {
+    parent.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Px(RING_SIZE), Val::Px(RING_SIZE)),
+            ..Default::default()
+        },
+        material: transparent,
+        ..Default::default()
+    })
+    .with_children(|parent| {
+        let radius = (RING_SIZE - DOT_SIZE) / 2.0;
+        for index in 0..capacity {
+            // clockwise from the top
+            let angle = std::f32::consts::FRAC_PI_2
+                - std::f32::consts::TAU * index as f32 / capacity as f32;
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(radius + radius * angle.cos()),
+                        bottom: Val::Px(radius + radius * angle.sin()),
+                        ..Default::default()
+                    },
+                    size: Size::new(Val::Px(DOT_SIZE), Val::Px(DOT_SIZE)),
+                    ..Default::default()
+                },
+                material: materials.add(EMPTY.into()),
+                ..Default::default()
+            })
+            .insert(NetDot { sensor, index });
+        }
+    });
+}
+
+fn spawn_controls(
+    parent: &mut ChildBuilder,
+    sensor: &'static str,
+    kind: MovementKind,
+    text_style: &TextStyle,
+    button: Handle<ColorMaterial>,
+    transparent: Handle<ColorMaterial>,
+) {
+    parent.spawn_bundle(NodeBundle {
+        style: Style {
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: transparent,
+        ..Default::default()
+    })
+    .with_children(|parent| {
+        let label = sensor.split('.').last().unwrap();
+        let spawn_button = |parent: &mut ChildBuilder, text: &str, direction: f32| {
+            parent.spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(20.0), Val::Px(20.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: button.clone(),
+                ..Default::default()
+            })
+            .insert(NetButton { sensor, kind, direction })
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(text, text_style.clone(), Default::default()),
+                    ..Default::default()
+                });
+            });
+        };
+        spawn_button(parent, "<", -1.0);
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(label, text_style.clone(), Default::default()),
+            ..Default::default()
+        });
+        spawn_button(parent, ">", 1.0);
+    });
+}
+
+pub(super) fn toggle_net(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut net: ResMut<NetView>,
+    mut query: Query<&mut Style, With<NetPanel>>,
+) {
+    if input_mode.keyboard() && key.just_pressed(KeyCode::M) {
+        net.visible = !net.visible;
+    }
+    if !net.is_changed() {
+        return;
+    }
+    for mut style in query.iter_mut() {
+        style.display = if net.visible { Display::Flex } else { Display::None };
+    }
+}
+
+/// Paints each dot after the ball in its slot, when the balls or the style change.
+pub(super) fn refresh_net(
+    cube: Res<CubeDescriptor>,
+    net: Res<NetView>,
+    style: Res<BallStyle>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    sensor_query: Query<(&Name, &SensorSlot, &BallSensor)>,
+    changed_query: Query<(), Changed<BallSensor>>,
+    ball_query: Query<(&PathHandle, &SlideHandle, &RotateHandle)>,
+    dot_query: Query<(&NetDot, &Handle<ColorMaterial>)>,
+) {
+    if !net.visible {
+        return;
+    }
+    if !net.is_changed() && !style.is_changed() && changed_query.iter().next().is_none() {
+        return;
+    }
+    let colors = style.colors();
+    for (name, slot, sensor) in sensor_query.iter() {
+        let ring = sensor.detail.iter()
+            .filter_map(|(entity, color, _)| {
+                let (path, slide, rotate) = ball_query.get(*entity).ok()?;
+                let index = cube.sensor_index(*slot, cube.ball_place(path, slide, rotate))?;
+                Some((index, colors[color.index()]))
+            })
+            .collect::<HashMap<_, _>>();
+        for (dot, handle) in dot_query.iter().filter(|(dot, _)| dot.sensor == name.as_str()) {
+            let color = ring.get(&dot.index).copied().unwrap_or(EMPTY);
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = color;
+            }
+        }
+    }
+}
+
+/// Buttons under each ring move it a single step, regardless of the cube side in view.
+pub(super) fn click_net(
+    cube: Res<CubeDescriptor>,
+    input_mode: Res<InputMode>,
+    mut blocked_events: EventWriter<MoveBlockedEvent>,
+    block_query: Query<&SlideHandle, With<Block>>,
+    button_query: Query<(&Interaction, &NetButton), Changed<Interaction>>,
+    sensor_query: Query<(Entity, &Name, &BallSensor)>,
+) -> Option<Movement> {
+    if !input_mode.mouse() {
+        return None;
+    }
+    let button = button_query.iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| button)?;
+    let step = match button.kind {
+        MovementKind::Path => cube.ball_step(),
+        MovementKind::Slide => 1.0,
+        MovementKind::Rotate => 0.5,
+    };
+    let is_slide_left = block_query.iter().next()?.t.to_f32() < 0.5;
+    input::request_movement(
+        button.sensor,
+        button.kind,
+        step * button.direction,
+        is_slide_left,
+        &sensor_query,
+        &mut blocked_events,
+    )
+}
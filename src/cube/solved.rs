@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+
+use crate::component::*;
+use super::{history, HandleChanged};
+
+/// Sends a `SolvedEvent` when the balls come back home under the current `SolveMode`.
+pub(super) fn check_solved(
+    cube: Res<CubeDescriptor>,
+    mode: Res<SolveMode>,
+    grab_status: Res<GrabStatus>,
+    mut was_solved: Local<bool>,
+    mut events: EventWriter<SolvedEvent>,
+    ball_query: Query<(&BallIndex, &PathHandle, &SlideHandle, &RotateHandle)>,
+    changed_query: Query<(), (With<BallIndex>, HandleChanged)>,
+) {
+    // mid-drag handles are between slots
+    if grab_status.grabbing.is_some() {
+        return;
+    }
+    if changed_query.iter().next().is_none() && !mode.is_changed() {
+        return;
+    }
+
+    let is_solved = history::puzzle_of(&cube, ball_query.iter())
+        .map_or(false, |puzzle| puzzle.is_solved(*mode));
+    if is_solved && !*was_solved {
+        info!("solved ({:?})", *mode);
+        events.send(SolvedEvent { mode: *mode });
+    }
+    *was_solved = is_solved;
+}
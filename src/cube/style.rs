@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use bevy::render::texture::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::component::*;
+
+use crate::util::{glyph, otry};
+
+const HOVER_EMISSIVE: f32 = 0.5;
+const GRAB_EMISSIVE: f32 = 1.2;
+
+pub(super) fn ball_materials(
+    style: &BallStyle,
+    symbols: &BallSymbols,
+    materials: &mut Assets<StandardMaterial>,
+) -> BallMaterials {
+    let mut add_materials = |emissive| -> Vec<Handle<StandardMaterial>> {
+        BallColor::ALL.iter()
+            .map(|color| {
+                let mut material = StandardMaterial::default();
+                style_material(&mut material, style, symbols, *color, emissive);
+                materials.add(material)
+            })
+            .collect()
+    };
+
+    BallMaterials {
+        normal: add_materials(0.0),
+        hover: add_materials(HOVER_EMISSIVE),
+        grab: add_materials(GRAB_EMISSIVE),
+    }
+}
+
+pub(super) fn ball_symbols(textures: &mut Assets<Texture>) -> BallSymbols {
+    let textures = BallColor::ALL.iter()
+        .map(|color| {
+            let text = (color.index() + 1).to_string();
+            textures.add(label_texture(&text, [255, 255, 255, 255], [30, 30, 30, 255]))
+        })
+        .collect();
+    BallSymbols { textures }
+}
+
+/// `paper` texture with `text` printed in `ink` four times around the
+/// equator of a sphere's uv map.
+pub(super) fn label_texture(text: &str, paper: [u8; 4], ink: [u8; 4]) -> Texture {
+    const WIDTH: usize = 128;
+    const HEIGHT: usize = 32;
+    const COPIES: usize = 4;
+    const SCALE: usize = 3;
+
+    let mut data = paper.repeat(WIDTH * HEIGHT);
+    let text_width = glyph::text_width(text, SCALE);
+    let oy = (HEIGHT - glyph::HEIGHT * SCALE) / 2;
+    for copy in 0..COPIES {
+        let center = WIDTH * (copy * 2 + 1) / (COPIES * 2);
+        let ox = center.saturating_sub(text_width / 2);
+        glyph::draw(text, SCALE, |x, y| {
+            let i = ((oy + y) * WIDTH + ox + x) * 4;
+            data[i..i + 4].copy_from_slice(&ink);
+        });
+    }
+
+    Texture::new(
+        Extent3d::new(WIDTH as u32, HEIGHT as u32, 1),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn style_material(
+    material: &mut StandardMaterial,
+    style: &BallStyle,
+    symbols: &BallSymbols,
+    color: BallColor,
+    emissive: f32,
+) {
+    let base_color = style.colors()[color.index()];
+    material.base_color = base_color;
+    material.emissive = Color::rgb(
+        base_color.r() * emissive,
+        base_color.g() * emissive,
+        base_color.b() * emissive,
+    );
+    material.roughness = style.roughness;
+    material.reflectance = style.reflectance;
+    material.base_color_texture = if style.symbols {
+        Some(symbols.textures[color.index()].clone())
+    } else {
+        None
+    };
+}
+
+/// Restyles the shared ball materials in place, so no ball is respawned.
+pub(super) fn apply_ball_style(
+    style: Res<BallStyle>,
+    ball_materials: Option<Res<BallMaterials>>,
+    symbols: Option<Res<BallSymbols>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let ball_materials = otry!(ball_materials);
+    let symbols = otry!(symbols);
+    if !style.is_changed() {
+        return;
+    }
+
+    let sets = [
+        (&ball_materials.normal, 0.0),
+        (&ball_materials.hover, HOVER_EMISSIVE),
+        (&ball_materials.grab, GRAB_EMISSIVE),
+    ];
+    for (handles, emissive) in sets.iter() {
+        for (color, handle) in BallColor::ALL.iter().zip(handles.iter()) {
+            if let Some(material) = materials.get_mut(handle) {
+                style_material(material, &style, &symbols, *color, *emissive);
+            }
+        }
+    }
+}
+
+/// Spawns `BallLabel` shells while labels are on or the numbered mode needs
+/// them, and removes them otherwise.
+pub(super) fn apply_ball_labels(
+    mut commands: Commands,
+    cube: Res<CubeDescriptor>,
+    style: Res<BallStyle>,
+    mode: Res<SolveMode>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    ball_query: Query<(Entity, &BallIndex)>,
+    added_query: Query<(), Added<BallIndex>>,
+    label_query: Query<Entity, With<BallLabel>>,
+) {
+    let is_added = added_query.iter().next().is_some();
+    if !style.is_changed() && !mode.is_changed() && !is_added {
+        return;
+    }
+    let show = style.labels || *mode == SolveMode::Numbered;
+    let shown = label_query.iter().next().is_some();
+    if show == shown {
+        return;
+    }
+
+    if !show {
+        for entity in label_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let radius = cube.ball_radians() * 1.02;
+    let mesh = meshes.add(Mesh::from(shape::Icosphere { radius, subdivisions: 12 }));
+    for (entity, index) in ball_query.iter() {
+        let texture = label_texture(&index.0.to_string(), [0, 0, 0, 0], [255, 255, 255, 255]);
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(textures.add(texture)),
+            unlit: true,
+            ..Default::default()
+        });
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material,
+                visible: Visible { is_visible: true, is_transparent: true },
+                ..Default::default()
+            })
+            .insert(BallLabel);
+        });
+    }
+}
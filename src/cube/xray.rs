@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use bevy::core::FloatOrd;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy_mod_picking::PickableMesh;
+
+use crate::component::*;
+use crate::util::otry;
+use super::ViewChanged;
+
+/// One side of a see-through mesh, drawn as a child in its place.
+struct XRayFace {
+    /// Node name, for `XRayConfig::opacity`.
+    name: String,
+    /// Outward normal of the side in cube space.
+    normal: Vec3,
+    material: Handle<StandardMaterial>,
+}
+
+/// Mesh hidden behind its `XRayFace`s.
+struct XRayHidden;
+
+pub(super) fn toggle_xray(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut config: ResMut<XRayConfig>,
+) {
+    if !input_mode.keyboard() {
+        return;
+    }
+    if key.just_pressed(KeyCode::V) {
+        config.enabled = !config.enabled;
+    }
+    if key.just_pressed(KeyCode::LBracket) {
+        config.front_opacity = (config.front_opacity - 0.1).max(0.0);
+    }
+    if key.just_pressed(KeyCode::RBracket) {
+        config.front_opacity = (config.front_opacity + 0.1).min(1.0);
+    }
+}
+
+fn translucent_material(material: &StandardMaterial) -> StandardMaterial {
+    StandardMaterial {
+        base_color: material.base_color,
+        base_color_texture: material.base_color_texture.clone(),
+        roughness: material.roughness,
+        metallic: material.metallic,
+        reflectance: material.reflectance,
+        emissive: material.emissive,
+        unlit: material.unlit,
+        double_sided: true,
+        ..Default::default()
+    }
+}
+
+/// Splits `mesh` by the side of the box its triangles face, as the outward
+/// axis of each side and a mesh of its triangles.
+fn split_sides(mesh: &Mesh) -> Vec<(Vec3, Mesh)> {
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float3(positions)) => positions,
+        _ => return Vec::new(),
+    };
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float3(normals)) => Some(normals),
+        _ => None,
+    };
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float2(uvs)) => Some(uvs),
+        _ => None,
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    let axes = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+    let mut sides = vec![Vec::new(); axes.len()];
+    for triangle in indices.chunks_exact(3) {
+        let p = |i: usize| Vec3::from(positions[triangle[i]]);
+        let normal = (p(1) - p(0)).cross(p(2) - p(0));
+        let side = (0..axes.len())
+            .max_by_key(|side| FloatOrd(axes[*side].dot(normal)))
+            .unwrap();
+        sides[side].extend_from_slice(triangle);
+    }
+
+    axes.iter()
+        .zip(sides)
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(axis, indices)| {
+            let mut side = Mesh::new(PrimitiveTopology::TriangleList);
+            side.set_attribute(Mesh::ATTRIBUTE_POSITION, pick(positions, &indices));
+            if let Some(normals) = normals {
+                side.set_attribute(Mesh::ATTRIBUTE_NORMAL, pick(normals, &indices));
+            }
+            if let Some(uvs) = uvs {
+                side.set_attribute(Mesh::ATTRIBUTE_UV_0, pick(uvs, &indices));
+            }
+            side.set_indices(Some(Indices::U32((0..indices.len() as u32).collect())));
+            (*axis, side)
+        })
+        .collect()
+}
+
+fn pick<T: Copy>(values: &[T], indices: &[usize]) -> Vec<T> {
+    indices.iter().map(|i| values[*i]).collect()
+}
+
+/// Draws the body and deco as translucent sides, and lets picking see
+/// through the body.
+pub(super) fn xray(
+    mut commands: Commands,
+    config: Res<XRayConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    body_query: Query<(Entity, &Parent), With<CubeBody>>,
+    deco_query: Query<(&Name, &Children), With<Deco>>,
+    name_query: Query<&Name>,
+    mut mesh_query: Query<(&Handle<Mesh>, &Handle<StandardMaterial>, &GlobalTransform, &mut Visible), Without<XRayFace>>,
+    hidden_query: Query<Entity, With<XRayHidden>>,
+    face_query: Query<Entity, With<XRayFace>>,
+    cube_query: Query<&GlobalTransform, With<Cube>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    let is_on = hidden_query.iter().next().is_some();
+
+    if config.enabled && !is_on {
+        let to_cube = otry!(cube_query.iter().next()).rotation.inverse();
+        let bodies = body_query.iter()
+            .filter_map(|(entity, parent)| Some((entity, name_query.get(parent.0).ok()?)));
+        let decos = deco_query.iter()
+            .flat_map(|(name, children)| children.iter().map(move |child| (*child, name)));
+        for (entity, name) in bodies.chain(decos) {
+            let (mesh, original, transform, mut visible) = match mesh_query.get_mut(entity) {
+                Ok(mesh) => mesh,
+                Err(_) => continue,
+            };
+            let material = match materials.get(original) {
+                Some(material) => translucent_material(material),
+                None => continue,
+            };
+            let sides = match meshes.get(mesh) {
+                Some(mesh) => split_sides(mesh),
+                None => continue,
+            };
+            visible.is_visible = false;
+            let rotation = to_cube * transform.rotation;
+
+            commands.entity(entity).insert(XRayHidden).with_children(|parent| {
+                for (normal, side) in sides {
+                    let material = materials.add(material.clone());
+                    parent
+                        .spawn_bundle(PbrBundle {
+                            mesh: meshes.add(side),
+                            material: material.clone(),
+                            visible: Visible { is_visible: true, is_transparent: true },
+                            ..Default::default()
+                        })
+                        .insert(XRayFace {
+                            name: name.as_str().to_string(),
+                            normal: rotation * normal,
+                            material,
+                        });
+                }
+            });
+        }
+        for (entity, _) in body_query.iter() {
+            commands.entity(entity).remove::<PickableMesh>();
+        }
+    }
+
+    if !config.enabled && is_on {
+        for entity in face_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for entity in hidden_query.iter() {
+            if let Ok((_, _, _, mut visible)) = mesh_query.get_mut(entity) {
+                visible.is_visible = true;
+            }
+            commands.entity(entity).remove::<XRayHidden>();
+        }
+        for (entity, _) in body_query.iter() {
+            commands.entity(entity).insert(PickableMesh::default());
+        }
+    }
+}
+
+/// Sets each side's opacity by whether it faces the camera, as the view or
+/// the config changes.
+pub(super) fn xray_opacity(
+    config: Res<XRayConfig>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    face_query: Query<&XRayFace>,
+    added_query: Query<(), Added<XRayFace>>,
+    cube_query: Query<&Transform, With<Cube>>,
+    view_query: Query<(), (With<Cube>, ViewChanged)>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+) {
+    let is_changed = config.is_changed()
+        || added_query.iter().next().is_some()
+        || view_query.iter().next().is_some();
+    if !config.enabled || !is_changed {
+        return;
+    }
+
+    let cube = otry!(cube_query.iter().next()).rotation;
+    let to_view = otry!(camera_query.iter().next()).rotation.inverse() * cube;
+    for face in face_query.iter() {
+        // the camera looks down its -z
+        let is_front = (to_view * face.normal).z >= 0.0;
+        let opacity = config.opacity.get(&face.name).copied().unwrap_or(
+            if is_front { config.front_opacity } else { config.back_opacity }
+        );
+        if let Some(material) = materials.get_mut(&face.material) {
+            material.base_color.set_a(opacity);
+        }
+    }
+}
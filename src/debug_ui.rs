@@ -5,6 +5,7 @@ use bevy_mod_picking::*;
 
 use crate::AppState;
 use crate::component::BallSensor;
+use crate::scene::LoadingAssets;
 
 struct FpsText;
 struct SensorDetailText;
@@ -18,7 +19,7 @@ impl Plugin for DebugUiPlugin {
             .add_startup_system(setup_fpi_ui.system())
             .add_system(fps_ui.system());
 
-        #[cfg(debug_assertions)]
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
         app.add_plugin(DebugCursorPickingPlugin)
             .add_startup_system(setup_detail_ui.system())
             .add_system_set(
@@ -32,9 +33,12 @@ fn setup_fpi_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut loading: ResMut<LoadingAssets>,
 ) {
+    let font = asset_server.load("FiraMono-Medium.ttf");
+    loading.track("font", &font);
     let style = TextStyle {
-        font: asset_server.load("FiraMono-Medium.ttf"),
+        font,
         font_size: 40.0,
         color: Color::rgb(0.0, 1.0, 1.0),
     };
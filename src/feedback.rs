@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::component::{MoveBlockedEvent, SolveMode, SolvedEvent};
+use crate::util::otry;
+
+struct Tooltip {
+    timer: Timer,
+}
+
+pub struct FeedbackConfig {
+    pub tooltip_seconds: f32,
+    /// Only has an effect with the `audio` feature.
+    pub sound: bool,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        FeedbackConfig {
+            tooltip_seconds: 2.0,
+            sound: true,
+        }
+    }
+}
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .init_resource::<FeedbackConfig>()
+            .add_startup_system(setup_tooltip.system())
+            .add_system(blocked_tooltip.system())
+            .add_system(solved_tooltip.system())
+            .add_system(hide_tooltip.system());
+
+        #[cfg(feature = "audio")]
+        app.add_system(blocked_sound.system());
+    }
+}
+
+fn setup_tooltip(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let style = TextStyle {
+        font: asset_server.load("FiraMono-Medium.ttf"),
+        font_size: 24.0,
+        color: Color::rgb(1.0, 0.8, 0.3),
+    };
+
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            align_self: AlignSelf::FlexEnd,
+            position_type: PositionType::Absolute,
+            position: Rect {
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..Default::default()
+            },
+            margin: Rect::all(Val::Px(2.0)),
+            ..Default::default()
+        },
+        text: Text::with_section("", style, Default::default()),
+        ..Default::default()
+    }).insert(Tooltip { timer: Timer::from_seconds(0.0, false) });
+}
+
+fn blocked_tooltip(
+    config: Res<FeedbackConfig>,
+    mut events: EventReader<MoveBlockedEvent>,
+    mut query: Query<(&mut Text, &mut Tooltip)>,
+) {
+    let ev = otry!(events.iter().last());
+
+    let (mut text, mut tooltip) = query.single_mut().unwrap();
+    text.sections[0].value = ev.reason.clone();
+    tooltip.timer = Timer::from_seconds(config.tooltip_seconds, false);
+}
+
+fn solved_tooltip(
+    config: Res<FeedbackConfig>,
+    mut events: EventReader<SolvedEvent>,
+    mut query: Query<(&mut Text, &mut Tooltip)>,
+) {
+    let ev = otry!(events.iter().last());
+
+    let (mut text, mut tooltip) = query.single_mut().unwrap();
+    text.sections[0].value = match ev.mode {
+        SolveMode::Colors => "solved!".to_string(),
+        SolveMode::Numbered => "solved, every ball is home!".to_string(),
+    };
+    tooltip.timer = Timer::from_seconds(config.tooltip_seconds, false);
+}
+
+fn hide_tooltip(
+    time: Res<Time>,
+    mut query: Query<(&mut Text, &mut Tooltip)>,
+) {
+    for (mut text, mut tooltip) in query.iter_mut() {
+        if tooltip.timer.tick(time.delta()).just_finished() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+fn blocked_sound(
+    config: Res<FeedbackConfig>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut events: EventReader<MoveBlockedEvent>,
+) {
+    if events.iter().count() > 0 && config.sound {
+        audio.play(asset_server.load("blocked.wav"));
+    }
+}
@@ -1,18 +1,22 @@
 use bevy::prelude::*;
-use bevy::input::mouse::MouseMotion;
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::input::touch::Touches;
 use bevy_mod_picking::{PickingCamera, Primitive3d};
 
 use crate::component::*;
+use crate::puzzle::{Move, GROUP_NAMES};
 
-use crate::util::{otry, range01::WrappingF32};
+use crate::util::otry;
 
 pub(super) fn cube_rotate(
     In(is_grabbing): In<bool>,
+    config: Res<ViewConfig>,
+    input_mode: Res<InputMode>,
     mouse: Res<Input<MouseButton>>,
     mut ev_motion: EventReader<MouseMotion>,
     mut query: Query<&mut CubeRotation>,
 ) {
-    if is_grabbing {
+    if is_grabbing || !input_mode.mouse() {
         return;
     }
 
@@ -22,23 +26,89 @@ pub(super) fn cube_rotate(
             movement += ev.delta;
         }
 
+        let movement = movement * config.orbit_sensitivity;
         let mut rotation = query.single_mut().unwrap();
-        rotation.yaw += movement.x / 100.0;
-        rotation.pitch += movement.y / 200.0;
+        if rotation.is_free() {
+            rotation.orbit(movement / 100.0);
+        } else {
+            rotation.yaw += movement.x / 100.0;
+            rotation.pitch += movement.y / 200.0;
+        }
+    }
+}
+
+pub(super) fn free_orbit(
+    mut commands: Commands,
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    config: Res<ViewConfig>,
+    mut query: Query<(Entity, &Transform, &mut CubeRotation)>,
+) {
+    if !input_mode.keyboard() || !key.just_pressed(KeyCode::F) {
+        return;
+    }
+
+    let (entity, transform, mut rotation) = otry!(query.single_mut().ok());
+    let free = !rotation.is_free();
+    rotation.set_free(free);
+    if !free {
+        // back to the locked range, ease into it
+        commands.entity(entity)
+            .insert(ViewAnimation::new(transform.rotation, &config));
     }
 }
 
+pub(super) fn zoom(
+    config: Res<ViewConfig>,
+    input_mode: Res<InputMode>,
+    touches: Res<Touches>,
+    mut ev_wheel: EventReader<MouseWheel>,
+    mut query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !input_mode.mouse() {
+        return;
+    }
+
+    let mut zoom = 0.0;
+    for ev in ev_wheel.iter() {
+        zoom += match ev.unit {
+            MouseScrollUnit::Line => ev.y,
+            MouseScrollUnit::Pixel => ev.y / 100.0,
+        };
+    }
+
+    let mut iter = touches.iter();
+    if let (Some(a), Some(b), None) = (iter.next(), iter.next(), iter.next()) {
+        let distance = a.position().distance(b.position());
+        let previous = a.previous_position().distance(b.previous_position());
+        zoom += (distance - previous) / 50.0;
+    }
+
+    if zoom == 0.0 {
+        return;
+    }
+
+    let mut transform = otry!(query.single_mut().ok());
+    let distance = transform.translation.length() - zoom * config.zoom_speed;
+    let distance = distance.clamp(config.min_distance, config.max_distance);
+    transform.translation = transform.translation.normalize() * distance;
+}
+
 pub(super) fn drag(
     cube: Res<CubeDescriptor>,
-    grab_status: Res<GrabStatus>,
+    config: Res<DragConfig>,
+    time: Res<Time>,
+    mut grab_status: ResMut<GrabStatus>,
     mut ev_motion: EventReader<MouseMotion>,
-    cube_query: Query<&CubeRotation>,
+    cube_query: Query<&Transform, With<Cube>>,
     picking_query: Query<&PickingCamera>,
 ) -> Option<Movement> {
     let grabbing = grab_status.grabbing?;
-    let cube_rotation = cube_query.single().unwrap();
+    // work in the cube space so any orientation gives the same directions
+    let inverse = cube_query.single().unwrap().rotation.inverse();
 
-    let picking = picking_query.single().unwrap();
+    // no picking camera when running headless
+    let picking = picking_query.single().ok()?;
     let picking_ray = picking.ray()?;
     let plane = Primitive3d::Plane {
             normal: picking_ray.direction(),
@@ -50,45 +120,63 @@ pub(super) fn drag(
     for ev in ev_motion.iter() {
         cursor_v += ev.delta;
     }
-    let movement = cursor_v.length();
+    let movement = cursor_v.length() * config.sensitivity;
+
+    let origin = inverse * grab_status.origin;
+    let cursor_pos = inverse * cursor_pos;
+    let local_v = inverse * Vec3::new(cursor_v.x, -cursor_v.y, 0.0);
 
     // speed down some
     let movement = match grabbing.kind {
         MovementKind::Path => {
-            let s = cube.path_sign(grab_status.origin, cursor_pos, cursor_v);
-            let s = if cube_rotation.is_face_back() { -s } else { s };
+            let s = cube.path_sign(origin, cursor_pos, Vec2::new(local_v.x, -local_v.y));
             movement.copysign(s) / 600.0
         }
-        MovementKind::Slide   => movement.copysign(cursor_v.x) / 100.0,
+        MovementKind::Slide   => movement.copysign(local_v.x) / 100.0,
         MovementKind::Rotate  => {
-            let s = if cube_rotation.is_face_back() { -cursor_v.x } else { cursor_v.x };
+            let s = local_v.dot(Vec3::Y.cross(origin));
             movement.copysign(s) / 200.0
         }
     };
 
+    let delta = time.delta_seconds();
+    if delta > 0.0 {
+        grab_status.velocity = grab_status.velocity * 0.5 + movement / delta * 0.5;
+    }
+
     Some(Movement { grabbing, movement })
 }
 
 pub(super) fn grab(
     mouse: Res<Input<MouseButton>>,
+    input_mode: Res<InputMode>,
     mut grab_status: ResMut<GrabStatus>,
     mut events: EventWriter<SnapEvent>,
+    mut blocked_events: EventWriter<MoveBlockedEvent>,
     picking_query: Query<&PickingCamera>,
-    sensor_query: Query<(&BallSensor, &MovementKind)>,
+    block_query: Query<&SlideHandle, With<Block>>,
+    sensor_query: Query<(&Name, &BallSensor, &MovementKind)>,
 ) -> bool {
-    if mouse.just_pressed(MouseButton::Left) {
-        let (entity, intersection) = otry!(picking_query.single().unwrap().intersect_top(), false);
-        let (sensor, kind) = otry!(sensor_query.get(entity).ok(), false);
+    if input_mode.mouse() && mouse.just_pressed(MouseButton::Left) {
+        let top = picking_query.single().ok().and_then(|picking| picking.intersect_top());
+        let (entity, intersection) = otry!(top, false);
+        let (name, sensor, kind) = otry!(sensor_query.get(entity).ok(), false);
         if sensor.is_full() {
             let grabbing = GrabbingSensor { kind: *kind, entity };
             grab_status.grabbing = Some(grabbing);
             grab_status.origin = intersection.position();
+            grab_status.velocity = 0.0;
+        } else {
+            let is_slide_left = block_query.iter().next().unwrap().t.to_f32() < 0.5;
+            let reason = blocked_reason(*kind, name.as_str(), is_slide_left);
+            blocked_events.send(MoveBlockedEvent { kind: *kind, sensor: entity, reason });
         }
     }
 
-    if mouse.just_released(MouseButton::Left) {
+    // also catches a release missed while paused
+    if !mouse.pressed(MouseButton::Left) {
         if let Some(grabbing) = grab_status.grabbing.take() {
-            events.send(SnapEvent(grabbing));
+            events.send(SnapEvent { grabbing, velocity: grab_status.velocity });
         }
     }
 
@@ -97,11 +185,17 @@ pub(super) fn grab(
 
 pub(super) fn key(
     key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
     cube: Res<CubeDescriptor>,
-    mut cube_query: Query<&mut CubeRotation>,
+    mut blocked_events: EventWriter<MoveBlockedEvent>,
+    cube_query: Query<&CubeRotation>,
     block_query: Query<&SlideHandle, With<Block>>,
     sensor_query: Query<(Entity, &Name, &BallSensor)>,
 ) -> Option<Movement> {
+    if !input_mode.keyboard() {
+        return None;
+    }
+
     let mut movement = None;
     if key.just_pressed(KeyCode::W) {
         movement = Some(("group", MovementKind::Path, cube.ball_step()));
@@ -124,21 +218,11 @@ pub(super) fn key(
         movement = Some(("block.rotate", MovementKind::Rotate, -0.5));
     }
 
-    if key.just_pressed(KeyCode::Q) {
-        let mut rotation = cube_query.single_mut().unwrap();
-        if rotation.is_face_back() {
-            rotation.pitch = WrappingF32::new(-0.05);
-        } else {
-            rotation.pitch = WrappingF32::new(0.45);
-        }
-        movement = None;
-    }
-
     let (name, kind, movement) = movement?;
-    let is_face_back = cube_query.single_mut().unwrap().is_face_back();
+    let is_face_back = cube_query.single().unwrap().is_face_back();
+    let is_slide_left = block_query.iter().next().unwrap().t.to_f32() < 0.5;
     let (name, movement) = match kind {
         MovementKind::Path => {
-            let is_slide_left = block_query.iter().next().unwrap().t.to_f32() < 0.5;
             let name = match (is_face_back, is_slide_left) {
                 (false,  true) => "group.a",
                 (false, false) => "group.b",
@@ -157,16 +241,143 @@ pub(super) fn key(
         }
     };
 
+    request_movement(name, kind, movement, is_slide_left, &sensor_query, &mut blocked_events)
+}
+
+/// Keeps every `PuzzleCommand` until its turn comes.
+pub(super) fn queue_commands(
+    mut events: EventReader<PuzzleCommand>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    queue.0.extend(events.iter().copied());
+}
+
+/// The oldest queued command, one per frame so every move starts from the
+/// balls at rest.
+pub(super) fn next_command(
+    grab_status: Res<GrabStatus>,
+    mut queue: ResMut<CommandQueue>,
+) -> Option<PuzzleCommand> {
+    // the held balls would snap from wherever they were put
+    if grab_status.grabbing.is_some() {
+        return None;
+    }
+    queue.0.pop_front()
+}
+
+/// Movement of a `PuzzleCommand::Move`.
+pub(super) fn command(
+    In(m): In<Option<Move>>,
+    cube: Res<CubeDescriptor>,
+    mut blocked_events: EventWriter<MoveBlockedEvent>,
+    block_query: Query<&SlideHandle, With<Block>>,
+    sensor_query: Query<(Entity, &Name, &BallSensor)>,
+) -> Option<Movement> {
+    let m = m?;
+    let sign = |forward: bool| if forward { 1.0 } else { -1.0 };
+    let (name, kind, movement) = match m {
+        Move::Turn { group, forward } => {
+            (GROUP_NAMES[group], MovementKind::Path, cube.ball_step() * sign(forward))
+        }
+        Move::Slide { right } => ("block.slide", MovementKind::Slide, sign(right)),
+        Move::Rotate => ("block.rotate", MovementKind::Rotate, 0.5),
+    };
+    let is_slide_left = block_query.iter().next()?.t.to_f32() < 0.5;
+    request_movement(name, kind, movement, is_slide_left, &sensor_query, &mut blocked_events)
+}
+
+/// Movement of the sensor called `name`, or a `MoveBlockedEvent` if it is not full.
+pub(super) fn request_movement(
+    name: &str,
+    kind: MovementKind,
+    movement: f32,
+    is_slide_left: bool,
+    sensor_query: &Query<(Entity, &Name, &BallSensor)>,
+    blocked_events: &mut EventWriter<MoveBlockedEvent>,
+) -> Option<Movement> {
     let (entity, _, sensor) = sensor_query.iter()
         .filter(|(_, n, _)| n.as_str() == name).next().unwrap();
     if sensor.is_full() {
         let grabbing = GrabbingSensor { kind, entity };
         Some(Movement { grabbing, movement })
     } else {
+        let reason = blocked_reason(kind, name, is_slide_left);
+        blocked_events.send(MoveBlockedEvent { kind, sensor: entity, reason });
         None
     }
 }
 
+fn blocked_reason(kind: MovementKind, name: &str, is_slide_left: bool) -> String {
+    let loops = if is_slide_left { "group.a and group.c" } else { "group.b and group.d" };
+    match kind {
+        MovementKind::Path => {
+            format!("{} is not under the slide block, slide the block over first", name)
+        }
+        MovementKind::Slide => format!("align {} with the slide block first", loops),
+        MovementKind::Rotate => format!("align {} with the rotate block first", loops),
+    }
+}
+
+pub(super) fn view(
+    mut commands: Commands,
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    config: Res<ViewConfig>,
+    mut query: Query<(Entity, &Transform, &mut CubeRotation)>,
+) {
+    if !input_mode.keyboard() {
+        return;
+    }
+    let is_flip = key.just_pressed(KeyCode::Q);
+    let is_reset = key.just_pressed(KeyCode::Z);
+    if !is_flip && !is_reset {
+        return;
+    }
+
+    let (entity, transform, mut rotation) = otry!(query.single_mut().ok());
+    if is_flip {
+        rotation.flip();
+    } else {
+        *rotation = CubeRotation::front();
+    }
+    commands.entity(entity)
+        .insert(ViewAnimation::new(transform.rotation, &config));
+}
+
+pub(super) fn ball_style(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut style: ResMut<BallStyle>,
+) {
+    if !input_mode.keyboard() {
+        return;
+    }
+    if key.just_pressed(KeyCode::C) {
+        style.palette = style.palette.next();
+        info!("ball palette: {}", style.palette.name());
+    }
+    if key.just_pressed(KeyCode::N) {
+        style.symbols = !style.symbols;
+    }
+    if key.just_pressed(KeyCode::I) {
+        style.labels = !style.labels;
+    }
+}
+
+pub(super) fn solve_mode(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut mode: ResMut<SolveMode>,
+) {
+    if input_mode.keyboard() && key.just_pressed(KeyCode::O) {
+        *mode = match *mode {
+            SolveMode::Colors => SolveMode::Numbered,
+            SolveMode::Numbered => SolveMode::Colors,
+        };
+        info!("solve mode: {:?}", *mode);
+    }
+}
+
 pub(super) fn apply_movement(
     In(movement): In<Option<Movement>>,
     mut block_query: QuerySet<(
@@ -232,39 +443,3 @@ fn rotate(
         handle.t += movement;
     }
 }
-
-pub(super) fn reset(
-    key: Res<Input<KeyCode>>,
-    cube: Res<CubeDescriptor>,
-    ball_query: Query<Entity, With<BallColor>>,
-    mut query: QuerySet<(
-        Query<(&mut PathHandle, &mut SlideHandle, &mut RotateHandle)>,
-        Query<&mut SlideHandle, With<Block>>,
-        Query<&mut RotateHandle, With<Block>>,
-    )>,
-) {
-    let is_shuffle = key.just_pressed(KeyCode::P);
-    let is_reset   = key.just_pressed(KeyCode::L);
-    if is_shuffle || is_reset {
-        let mut balls = ball_query.iter().collect::<Vec<_>>();
-        if is_shuffle {
-            fastrand::shuffle(&mut balls);
-        } else {
-            balls.sort();
-        }
-        for (entity, init) in balls.into_iter().zip(cube.ball_init_handle_iter()) {
-            let mut handle = query.q0_mut().get_mut(entity).unwrap();
-            *handle.0 = init.path;
-            *handle.1 = init.slide;
-            *handle.2 = init.rotate;
-        }
-
-        for mut handle in query.q1_mut().iter_mut() {
-            *handle = SlideHandle::left();
-        }
-
-        for mut handle in query.q2_mut().iter_mut() {
-            *handle = RotateHandle::up();
-        }
-    }
-}
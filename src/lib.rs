@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy::ecs::schedule::ShouldRun;
 use wasm_bindgen::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
@@ -8,27 +9,62 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 mod scene;
 mod cube;
 mod input;
-mod component;
+pub mod component;
 mod debug_ui;
+mod feedback;
+mod theme;
+mod settings;
+mod pause;
+mod plugin;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
 mod util;
 
+pub mod puzzle;
+pub mod solver;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use plugin::MagicBeanPlugin;
+pub use scene::SceneSource;
+pub use util::bezier;
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub enum AppState {
     Load,
     Setup,
     InGame,
-    Pause,
+    /// A snap animation is running, moves are locked.
+    Animating,
+    /// Paused by the user, pushed over `InGame` or `Animating`.
+    Paused,
+    Error,
+    /// Settings menu, pushed over `Paused`.
+    Menu,
+}
+
+/// Run criteria for the systems that stop while the user has paused.
+pub(crate) fn not_paused(state: Res<State<AppState>>) -> ShouldRun {
+    match state.current() {
+        AppState::Paused | AppState::Menu => ShouldRun::No,
+        _ => ShouldRun::Yes,
+    }
 }
 
 #[wasm_bindgen]
 pub fn run() {
     let mut app = App::build();
-    app.add_plugin(scene::ScenePlugin)
-        .add_plugin(cube::CubePlugin);
+    let settings = settings::Settings::load();
+    // the renderer reads the MSAA samples once, as it starts
+    app.insert_resource(Msaa { samples: settings.msaa_samples })
+        .insert_resource(settings)
+        .add_plugins(DefaultPlugins);
 
-    #[cfg(not(feature = "public"))]
-    app.add_plugin(debug_ui::DebugUiPlugin);
+    // when building for Web, use WebGL2 rendering
+    #[cfg(target_arch = "wasm32")]
+    app.add_plugin(bevy_webgl2::WebGL2Plugin);
 
-    app.run();
+    app.add_plugin(MagicBeanPlugin::default().hot_reload_theme(true))
+        .run();
 }
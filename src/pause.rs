@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::component::InputMode;
+use crate::cube::Stage;
+use crate::util::otry;
+
+/// Time spent playing, stopped while paused.
+#[derive(Default)]
+pub struct PlayClock {
+    pub elapsed: std::time::Duration,
+}
+
+struct PauseScreen;
+
+enum PauseButton {
+    Resume,
+    Settings,
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .init_resource::<PlayClock>()
+            // a snap starting or ending goes first, the pause follows a frame later
+            .add_system(toggle_pause.system().after(Stage::Snap))
+            .add_system_set(SystemSet::on_update(AppState::InGame)
+                .with_system(play_clock.system())
+            )
+            .add_system_set(SystemSet::on_update(AppState::Animating)
+                .with_system(play_clock.system())
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused)
+                .with_system(setup_pause_screen.system())
+            )
+            .add_system_set(SystemSet::on_update(AppState::Paused)
+                .with_system(pause_buttons.system())
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Paused)
+                .with_system(clear_pause_screen.system())
+            );
+    }
+}
+
+fn play_clock(time: Res<Time>, mut clock: ResMut<PlayClock>) {
+    clock.elapsed += time.delta();
+}
+
+/// Escape pauses a game, and steps back out of the settings and pause screens.
+fn toggle_pause(
+    key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut state: ResMut<State<AppState>>,
+    mut is_pending: Local<bool>,
+) {
+    if input_mode.keyboard() && key.just_pressed(KeyCode::Escape) {
+        *is_pending = true;
+    }
+    if !*is_pending {
+        return;
+    }
+    let result = match state.current() {
+        AppState::InGame | AppState::Animating => state.push(AppState::Paused),
+        AppState::Paused | AppState::Menu => state.pop(),
+        _ => Ok(()),
+    };
+    // another transition is queued this frame, try again on the next
+    *is_pending = result.is_err();
+}
+
+fn setup_pause_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    clock: Res<PlayClock>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let style = TextStyle {
+        font: asset_server.load("FiraMono-Medium.ttf"),
+        font_size: 30.0,
+        color: Color::WHITE,
+    };
+    let button = materials.add(Color::rgb(0.2, 0.2, 0.2).into());
+
+    let seconds = clock.elapsed.as_secs();
+    let clock_text = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        },
+        // dims the scene
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.5).into()),
+        ..Default::default()
+    })
+    .insert(PauseScreen)
+    .with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            text: Text::with_section("Paused", style.clone(), Default::default()),
+            ..Default::default()
+        });
+        parent.spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            text: Text::with_section(clock_text, style.clone(), Default::default()),
+            ..Default::default()
+        });
+
+        let buttons = [("Resume", PauseButton::Resume), ("Settings", PauseButton::Settings)];
+        for (text, action) in std::array::IntoIter::new(buttons) {
+            parent.spawn_bundle(ButtonBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(8.0)),
+                    padding: Rect::all(Val::Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: button.clone(),
+                ..Default::default()
+            })
+            .insert(action)
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(text, style.clone(), Default::default()),
+                    ..Default::default()
+                });
+            });
+        }
+    });
+}
+
+fn pause_buttons(
+    mut state: ResMut<State<AppState>>,
+    query: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+) {
+    let (_, button) = otry!(query.iter().find(|(interaction, _)| **interaction == Interaction::Clicked));
+    match button {
+        PauseButton::Resume => state.pop().unwrap(),
+        PauseButton::Settings => state.push(AppState::Menu).unwrap(),
+    }
+}
+
+fn clear_pause_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<PauseScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::component::{CubeDescriptor, InputMode, XRayConfig};
+use crate::scene::{ScenePlugin, SceneSource};
+use crate::theme::ThemeConfig;
+use crate::{cube, debug_ui, feedback, pause, settings, theme};
+
+/// The whole puzzle, for a host app that has added `DefaultPlugins`:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use magic_bean::MagicBeanPlugin;
+/// use magic_bean::component::InputMode;
+///
+/// App::build()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(MagicBeanPlugin::default().input_mode(InputMode::External))
+///     .run();
+/// ```
+///
+/// Observe it through `SolvedEvent`, `MoveBlockedEvent` and the `History`
+/// resource, and drive it with `PuzzleCommand` events.
+pub struct MagicBeanPlugin {
+    descriptor: CubeDescriptor,
+    scene: SceneSource,
+    camera: bool,
+    lights: bool,
+    hot_reload_theme: bool,
+    input_mode: InputMode,
+    xray: XRayConfig,
+    debug_ui: bool,
+}
+
+impl Default for MagicBeanPlugin {
+    fn default() -> Self {
+        MagicBeanPlugin {
+            descriptor: CubeDescriptor::default(),
+            scene: SceneSource::default(),
+            camera: true,
+            lights: true,
+            hot_reload_theme: false,
+            input_mode: InputMode::default(),
+            xray: XRayConfig::default(),
+            debug_ui: !cfg!(feature = "public"),
+        }
+    }
+}
+
+impl MagicBeanPlugin {
+    /// Ball loops and dimensions, the glTF scene may still replace the loops.
+    pub fn descriptor(mut self, descriptor: CubeDescriptor) -> Self {
+        self.descriptor = descriptor;
+        self
+    }
+
+    pub fn scene(mut self, scene: SceneSource) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    /// Spawn a camera, otherwise tag your own with `MainCamera`.
+    pub fn camera(mut self, camera: bool) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Spawn the theme's lights.
+    pub fn lights(mut self, lights: bool) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// Watch the asset files, for editing the theme while the game runs.
+    pub fn hot_reload_theme(mut self, hot_reload_theme: bool) -> Self {
+        self.hot_reload_theme = hot_reload_theme;
+        self
+    }
+
+    pub fn input_mode(mut self, input_mode: InputMode) -> Self {
+        self.input_mode = input_mode;
+        self
+    }
+
+    /// See-through body and deco, and their opacity by side or node name.
+    pub fn xray(mut self, xray: XRayConfig) -> Self {
+        self.xray = xray;
+        self
+    }
+
+    /// FPS counter and sensor details.
+    pub fn debug_ui(mut self, debug_ui: bool) -> Self {
+        self.debug_ui = debug_ui;
+        self
+    }
+}
+
+impl Plugin for MagicBeanPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .insert_resource(self.descriptor.clone())
+            .insert_resource(self.scene)
+            .insert_resource(self.input_mode)
+            .insert_resource(self.xray.clone())
+            .insert_resource(ThemeConfig {
+                lights: self.lights,
+                hot_reload: self.hot_reload_theme,
+                ..Default::default()
+            })
+            .add_plugin(ScenePlugin { spawn_camera: self.camera })
+            .add_plugin(cube::CubePlugin)
+            .add_plugin(feedback::FeedbackPlugin)
+            .add_plugin(theme::ThemePlugin)
+            .add_plugin(settings::SettingsPlugin)
+            .add_plugin(pause::PausePlugin);
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_plugin(crate::web::WebPlugin);
+
+        if self.debug_ui {
+            app.add_plugin(debug_ui::DebugUiPlugin);
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! The puzzle as plain data: which ball sits in which slot of the four loops,
+//! the moves between them, scrambling, undo and the solved check. Shared by
+//! the game and the console front-end, so nothing here depends on Bevy.
+
+use std::fmt;
+
+/// Slots around one loop.
+pub const LOOP_LEN: usize = 18;
+pub const N_LOOPS: usize = 4;
+/// Slots of a loop carried across by the slide block.
+pub const WINDOW_LEN: usize = 4;
+/// Slots of a loop swapped front to back by the rotate block.
+pub const ROTATE_WINDOW: std::ops::Range<usize> = 1..3;
+pub const N_BALLS: usize = LOOP_LEN * 2 + (LOOP_LEN - WINDOW_LEN) * 2;
+pub const BALLS_PER_COLOR: usize = N_BALLS / N_LOOPS;
+pub const COLOR_CHARS: [char; N_LOOPS] = ['A', 'B', 'C', 'D'];
+/// Random moves of a `scramble`.
+pub const SCRAMBLE_MOVES: usize = 200;
+
+/// Loop names in group order: left up, right up, left down, right down.
+pub const GROUP_NAMES: [&str; N_LOOPS] = ["group.a", "group.b", "group.c", "group.d"];
+
+/// What counts as solved.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolveMode {
+    /// Every loop holds its own color, and the window two balls of each
+    /// loop on its face, see `home`.
+    Colors,
+    /// Every ball is back in its own home slot.
+    Numbered,
+}
+
+impl Default for SolveMode {
+    fn default() -> Self {
+        SolveMode::Colors
+    }
+}
+
+/// Color index of ball number `ball`, each color numbers `BALLS_PER_COLOR`
+/// balls in a row (see `home`).
+pub fn color(ball: u8) -> usize {
+    ball as usize / BALLS_PER_COLOR
+}
+
+/// Number of the ball whose home is `index` of loop `group`, with the blocks
+/// on the left. Solved, each loop holds its own color outside the window,
+/// and the window holds two balls of each loop on its face, the left
+/// loop's first.
+pub fn home(group: usize, index: usize) -> Option<u8> {
+    const HALF: usize = WINDOW_LEN / 2;
+    let (color, rank) = if index >= WINDOW_LEN {
+        (group, index - HALF)
+    } else if group % 2 == 0 {
+        (group + index / HALF, index % HALF)
+    } else {
+        return None;
+    };
+    Some((color * BALLS_PER_COLOR + rank) as u8)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Move {
+    /// Turns loop `group` one slot, towards higher slots if `forward`.
+    Turn { group: usize, forward: bool },
+    /// Carries the window of the up and down loops to the right or left side.
+    Slide { right: bool },
+    /// Swaps the rotate window of the up and down loops under the block.
+    Rotate,
+}
+
+impl Move {
+    /// The move taking the puzzle back.
+    pub fn inverse(&self) -> Move {
+        match *self {
+            Move::Turn { group, forward } => Move::Turn { group, forward: !forward },
+            Move::Slide { right } => Move::Slide { right: !right },
+            Move::Rotate => Move::Rotate,
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Move::Turn { group, forward } => {
+                let sign = if *forward { '+' } else { '-' };
+                write!(f, "{}{}", GROUP_NAMES[*group], sign)
+            }
+            Move::Slide { right: true } => write!(f, "slide>"),
+            Move::Slide { right: false } => write!(f, "slide<"),
+            Move::Rotate => write!(f, "rotate"),
+        }
+    }
+}
+
+impl std::str::FromStr for Move {
+    type Err = ParseError;
+
+    /// Parses a move as `Display` writes it, like `group.a+` or `slide<`.
+    fn from_str(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        let m = match text {
+            "slide>" => Move::Slide { right: true },
+            "slide<" => Move::Slide { right: false },
+            "rotate" => Move::Rotate,
+            _ => {
+                let (name, forward) = match text.char_indices().last() {
+                    Some((i, '+')) => (&text[..i], true),
+                    Some((i, '-')) => (&text[..i], false),
+                    _ => return Err(ParseError(format!("unknown move '{}'", text))),
+                };
+                let group = GROUP_NAMES.iter()
+                    .position(|x| *x == name)
+                    .ok_or_else(|| ParseError(format!("unknown loop '{}'", name)))?;
+                Move::Turn { group, forward }
+            }
+        };
+        Ok(m)
+    }
+}
+
+/// Why a move cannot be made.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    /// The loop is missing its window, it is under the other side.
+    NotUnderBlock { group: usize },
+    /// The block is already on that side.
+    AlreadySlid,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::NotUnderBlock { group } => {
+                write!(f, "{} is not under the slide block, slide the block over first", GROUP_NAMES[*group])
+            }
+            MoveError::AlreadySlid => write!(f, "the slide block is already there"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Ball number in every slot of the four loops, `None` for the window slots
+/// of the side the blocks are not on.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Puzzle {
+    loops: [[Option<u8>; LOOP_LEN]; N_LOOPS],
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Puzzle::solved()
+    }
+}
+
+impl Puzzle {
+    /// Every ball at home, the blocks on the left.
+    pub fn solved() -> Self {
+        let mut loops = [[None; LOOP_LEN]; N_LOOPS];
+        for (group, slots) in loops.iter_mut().enumerate() {
+            for (index, slot) in slots.iter_mut().enumerate() {
+                *slot = home(group, index);
+            }
+        }
+        Puzzle { loops }
+    }
+
+    /// Puzzle from `(ball, group, index)` places, `None` unless they fill
+    /// every slot of one side exactly once.
+    pub fn from_places(places: impl IntoIterator<Item=(u8, usize, usize)>) -> Option<Self> {
+        let mut loops = [[None; LOOP_LEN]; N_LOOPS];
+        let mut count = 0;
+        for (ball, group, index) in places {
+            let slot = loops.get_mut(group)?.get_mut(index)?;
+            if slot.is_some() || ball as usize >= N_BALLS {
+                return None;
+            }
+            *slot = Some(ball);
+            count += 1;
+        }
+        let puzzle = Puzzle { loops };
+        if count == N_BALLS && puzzle.is_consistent() {
+            Some(puzzle)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a `code`, numbering the balls of each color in slot order.
+    pub fn from_code(code: &str) -> Result<Self, ParseError> {
+        let rows = code.trim().split('/').collect::<Vec<_>>();
+        if rows.len() != N_LOOPS {
+            return Err(ParseError(format!("expected {} loops, got {}", N_LOOPS, rows.len())));
+        }
+
+        let mut loops = [[None; LOOP_LEN]; N_LOOPS];
+        let mut counts = [0; N_LOOPS];
+        for (group, row) in rows.iter().enumerate() {
+            let chars = row.chars().collect::<Vec<_>>();
+            if chars.len() != LOOP_LEN {
+                return Err(ParseError(format!("{} has {} slots, expected {}", GROUP_NAMES[group], chars.len(), LOOP_LEN)));
+            }
+            for (index, c) in chars.into_iter().enumerate() {
+                if c == '.' {
+                    continue;
+                }
+                let color = COLOR_CHARS.iter()
+                    .position(|x| *x == c.to_ascii_uppercase())
+                    .ok_or_else(|| ParseError(format!("unknown ball '{}'", c)))?;
+                if counts[color] == BALLS_PER_COLOR {
+                    return Err(ParseError(format!("more than {} '{}' balls", BALLS_PER_COLOR, c)));
+                }
+                loops[group][index] = Some((color * BALLS_PER_COLOR + counts[color]) as u8);
+                counts[color] += 1;
+            }
+        }
+
+        let puzzle = Puzzle { loops };
+        if counts.iter().any(|c| *c != BALLS_PER_COLOR) {
+            return Err(ParseError(format!("expected {} balls of each color", BALLS_PER_COLOR)));
+        }
+        if !puzzle.is_consistent() {
+            return Err(ParseError("only the window slots of one side can be empty".to_string()));
+        }
+        Ok(puzzle)
+    }
+
+    /// Parses the four `group.*` sensors as the game prints them, the balls
+    /// of each loop in slot order without the empty window. Sensors are
+    /// separated by whitespace or commas, in group order or named like
+    /// `group.b=BBAB...`.
+    pub fn from_sensors(text: &str) -> Result<Self, ParseError> {
+        let mut rows = vec![None; N_LOOPS];
+        let sensors = text.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty());
+        for (position, sensor) in sensors.enumerate() {
+            let (group, balls) = match sensor.find(|c| c == '=' || c == ':') {
+                Some(i) => {
+                    let name = &sensor[..i];
+                    let group = GROUP_NAMES.iter()
+                        .position(|n| *n == name || n.trim_start_matches("group.") == name)
+                        .ok_or_else(|| ParseError(format!("unknown sensor {}", name)))?;
+                    (group, &sensor[i + 1..])
+                }
+                None => (position, sensor),
+            };
+            let row = rows.get_mut(group)
+                .ok_or_else(|| ParseError(format!("more than {} sensors", N_LOOPS)))?;
+            *row = match balls.chars().count() {
+                LOOP_LEN => Some(balls.to_string()),
+                n if n == LOOP_LEN - WINDOW_LEN => Some(format!("{}{}", ".".repeat(WINDOW_LEN), balls)),
+                n => return Err(ParseError(format!("{} holds {} balls", GROUP_NAMES[group], n))),
+            };
+        }
+        let rows = rows.into_iter()
+            .enumerate()
+            .map(|(group, row)| row.ok_or_else(|| ParseError(format!("missing {}", GROUP_NAMES[group]))))
+            .collect::<Result<Vec<_>, _>>()?;
+        Puzzle::from_code(&rows.join("/"))
+    }
+
+    /// The loops as color letters, `.` for empty slots, joined by `/`.
+    pub fn code(&self) -> String {
+        self.loops.iter()
+            .map(|slots| {
+                slots.iter()
+                    .map(|slot| slot.map_or('.', |ball| COLOR_CHARS[color(ball)]))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn is_consistent(&self) -> bool {
+        let side = if self.is_block_left() { 0 } else { 1 };
+        self.loops.iter().enumerate().all(|(group, slots)| {
+            slots.iter().enumerate().all(|(index, slot)| {
+                let is_gap = group % 2 != side && index < WINDOW_LEN;
+                slot.is_none() == is_gap
+            })
+        })
+    }
+
+    pub fn is_block_left(&self) -> bool {
+        self.loops[0][0].is_some()
+    }
+
+    /// The up and down loops under the blocks.
+    pub fn block_groups(&self) -> [usize; 2] {
+        if self.is_block_left() { [0, 2] } else { [1, 3] }
+    }
+
+    pub fn get(&self, group: usize, index: usize) -> Option<u8> {
+        self.loops[group][index]
+    }
+
+    /// Every ball as `(ball, group, index)`.
+    pub fn places(&self) -> impl Iterator<Item=(u8, usize, usize)> + '_ {
+        self.loops.iter().enumerate().flat_map(|(group, slots)| {
+            slots.iter().enumerate()
+                .filter_map(move |(index, slot)| slot.map(|ball| (ball, group, index)))
+        })
+    }
+
+    pub fn check(&self, m: Move) -> Result<(), MoveError> {
+        match m {
+            Move::Turn { group, .. } if !self.block_groups().contains(&group) => {
+                Err(MoveError::NotUnderBlock { group })
+            }
+            Move::Slide { right } if right != self.is_block_left() => Err(MoveError::AlreadySlid),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn apply(&mut self, m: Move) -> Result<(), MoveError> {
+        self.check(m)?;
+        match m {
+            Move::Turn { group, forward } => {
+                let slots = &mut self.loops[group];
+                if forward {
+                    slots.rotate_right(1);
+                } else {
+                    slots.rotate_left(1);
+                }
+            }
+            Move::Slide { right } => {
+                let (from, to) = if right { (0, 1) } else { (1, 0) };
+                for up_down in [0, 2].iter() {
+                    for index in 0..WINDOW_LEN {
+                        let ball = self.loops[from + up_down][index].take();
+                        self.loops[to + up_down][index] = ball;
+                    }
+                }
+            }
+            Move::Rotate => {
+                let [up, down] = self.block_groups();
+                for index in ROTATE_WINDOW {
+                    let ball = self.loops[up][index];
+                    self.loops[up][index] = self.loops[down][index];
+                    self.loops[down][index] = ball;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every move `apply` accepts now.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(6);
+        for group in self.block_groups().iter() {
+            for forward in [true, false].iter() {
+                moves.push(Move::Turn { group: *group, forward: *forward });
+            }
+        }
+        moves.push(Move::Slide { right: self.is_block_left() });
+        moves.push(Move::Rotate);
+        moves
+    }
+
+    /// The one move from `self` to `other`, if there is one.
+    pub fn move_to(&self, other: &Puzzle) -> Option<Move> {
+        self.legal_moves().into_iter().find(|m| {
+            let mut puzzle = self.clone();
+            puzzle.apply(*m).is_ok() && puzzle == *other
+        })
+    }
+
+    /// Applies `moves` random legal moves, then slides the blocks back left.
+    pub fn scramble(&mut self, rng: &fastrand::Rng, moves: usize) {
+        for _ in 0..moves {
+            let legal = self.legal_moves();
+            let m = legal[rng.usize(..legal.len())];
+            self.apply(m).unwrap();
+        }
+        if !self.is_block_left() {
+            self.apply(Move::Slide { right: false }).unwrap();
+        }
+    }
+
+    pub fn is_solved(&self, mode: SolveMode) -> bool {
+        self.places().all(|(ball, group, index)| match (home(group, index), mode) {
+            (Some(home), SolveMode::Colors) => color(home) == color(ball),
+            (Some(home), SolveMode::Numbered) => home == ball,
+            (None, _) => false,
+        })
+    }
+}
+
+/// Every state the puzzle went through, to step back to.
+pub struct History {
+    states: Vec<Puzzle>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(Puzzle::solved())
+    }
+}
+
+impl History {
+    pub fn new(start: Puzzle) -> Self {
+        History { states: vec![start] }
+    }
+
+    pub fn current(&self) -> &Puzzle {
+        self.states.last().unwrap()
+    }
+
+    /// Moves made since the start.
+    pub fn moves(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    /// Records `puzzle` unless it is the current state already.
+    pub fn push(&mut self, puzzle: Puzzle) {
+        if *self.current() != puzzle {
+            self.states.push(puzzle);
+        }
+    }
+
+    /// Drops the current state, returning the one before, if any.
+    pub fn undo(&mut self) -> Option<&Puzzle> {
+        if self.states.len() < 2 {
+            return None;
+        }
+        self.states.pop();
+        Some(self.current())
+    }
+}
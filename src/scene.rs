@@ -1,46 +1,81 @@
 use bevy::prelude::*;
+use bevy::gltf::Gltf;
 use bevy_mod_picking::*;
 
 use crate::AppState;
+use crate::settings::Settings;
 use crate::component::*;
 use crate::component::debug::*;
 
-pub struct ScenePlugin;
+mod procedural;
+mod load;
+mod curve;
 
-impl ScenePlugin {
-    #[cfg(not(target_family = "wasm"))]
-    fn add_default_plugin(&self, app: &mut AppBuilder) {
-        app.insert_resource(Msaa { samples: 4 })
-            .add_plugins(DefaultPlugins);
-    }
+pub use load::{LoadingAssets, SceneError};
+
+/// Where the cube geometry comes from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SceneSource {
+    Gltf,
+    /// Built from `CubeDescriptor`, so any configured dimension fits.
+    Procedural,
+}
 
-    #[cfg(target_family = "wasm")]
-    fn add_default_plugin(&self, app: &mut AppBuilder) {
-        app.insert_resource(Msaa { samples: 4 })
-            .add_plugins(DefaultPlugins)
-            // when building for Web, use WebGL2 rendering
-            .add_plugin(bevy_webgl2::WebGL2Plugin);
+impl Default for SceneSource {
+    fn default() -> Self {
+        if cfg!(feature = "procedural") {
+            SceneSource::Procedural
+        } else {
+            SceneSource::Gltf
+        }
     }
 }
 
+/// The camera `setup_camera` spawned, a host app's camera keeps its place.
+struct SpawnedCamera;
+
+pub struct ScenePlugin {
+    /// Spawn the 3D and UI cameras, otherwise the host app tags its own
+    /// camera with `MainCamera`.
+    pub spawn_camera: bool,
+}
+
 impl Plugin for ScenePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        self.add_default_plugin(app);
+        if self.spawn_camera {
+            app
+                .add_startup_system(setup_ui.system())
+                .add_startup_system(setup_camera.system());
+        }
 
         app
             .add_plugin(PickingPlugin)
             .insert_resource(Debug::Off)
+            .init_resource::<SceneSource>()
+            .init_resource::<LoadingAssets>()
             .add_state(AppState::Load)
-            .add_startup_system(setup_ui.system())
             .add_system(debug.system())
             .add_system_set(SystemSet::on_enter(AppState::Load)
                 .with_system(setup_scene.system())
+                .with_system(load::setup_loading_screen.system())
             )
             .add_system_set(SystemSet::on_update(AppState::Load)
-                .with_system(load_complation.system())
+                .with_system(load::load_complation.system())
+                .with_system(load::loading_progress.system())
             )
             .add_system_set(SystemSet::on_exit(AppState::Load)
                 .with_system(tag_entity.system())
+                .with_system(curve::load_ball_path.system())
+                .with_system(load::clear_loading_screen.system())
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Error)
+                .with_system(load::setup_error_screen.system())
+            )
+            .add_system_set(SystemSet::on_update(AppState::Error)
+                .with_system(load::retry.system())
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Error)
+                .with_system(load::clear_error_screen.system())
             )
             .add_system_set(SystemSet::on_update(AppState::Setup)
                 .with_system(setup_complation.system())
@@ -48,31 +83,46 @@ impl Plugin for ScenePlugin {
     }
 }
 
-fn setup_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
-    if cfg!(feature = "public") {
-        commands.spawn_scene(asset_server.load("untitled6.glb#Scene0"));
-    } else {
-        commands.spawn_scene(asset_server.load("untitled6.gltf#Scene0"));
-    }
-
-    // light
-    let lights = [
-        (Vec3::new(-9.0, 9.0, -8.0), 800.0),
-        (Vec3::new(6.0, 6.0, 10.0), 3000.0),
-        (Vec3::new(-9.0, 6.0, 10.0), 200.0),
-    ];
-    for (point, intensity) in std::array::IntoIter::new(lights) {
-        commands.spawn_bundle(LightBundle {
-            light: Light {
-                intensity,
-                ..Default::default()
-            },
-            transform: Transform::from_translation(point),
-            ..Default::default()
-        });
-    }
+fn setup_scene(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    source: Res<SceneSource>,
+    cube: Res<CubeDescriptor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    // the scene nodes go under one root, a retry drops only them
+    let root = commands
+        .spawn_bundle((Transform::identity(), GlobalTransform::identity()))
+        .id();
+    let handle = match *source {
+        SceneSource::Gltf => {
+            let path = if cfg!(feature = "public") {
+                "untitled6.glb"
+            } else {
+                "untitled6.gltf"
+            };
+            let gltf: Handle<Gltf> = asset_server.load(path);
+            let handle = asset_server.load(format!("{}#Scene0", path).as_str());
+            loading.track("model", &gltf);
+            loading.track("scene", &handle);
+            commands.entity(root).with_children(|parent| {
+                parent.spawn_scene(handle.clone());
+            });
+            Some(handle)
+        }
+        SceneSource::Procedural => {
+            let cube_entity = procedural::spawn_cube(&mut commands, &cube, &mut meshes, &mut materials);
+            commands.entity(root).push_children(&[cube_entity]);
+            None
+        }
+    };
+    commands.insert_resource(load::LoadingScene::new(handle, root));
+}
 
-    // camera
+/// Lights come from the theme rig.
+fn setup_camera(mut commands: Commands) {
     let point = Vec3::new(0.0, 0.0, 10.0);
     let slash_point = Vec3::new(0.0, 0.0, 20.0);
     commands
@@ -80,22 +130,8 @@ fn setup_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
             transform: Transform::from_translation(point).looking_at(slash_point, Vec3::Y),
             ..Default::default()
         })
-        .insert(MainCamera);
-}
-
-fn load_complation(
-    mut state: ResMut<State<AppState>>,
-    mut events: EventReader<AssetEvent<Scene>>
-) {
-    for ev in events.iter() {
-        match ev {
-            AssetEvent::Created { .. } => {
-                //eprintln!("load");
-                state.set(AppState::Setup).unwrap();
-            },
-            _ => (),
-        }
-    }
+        .insert(MainCamera)
+        .insert(SpawnedCamera);
 }
 
 fn tag_entity(mut commands: Commands, query: Query<(Entity, &Name)>) {
@@ -108,6 +144,8 @@ fn tag_entity(mut commands: Commands, query: Query<(Entity, &Name)>) {
             commands.entity(entity).insert(Block::Slide);
         } else if name == "block.rotate" {
             commands.entity(entity).insert(Block::Rotate);
+        } else if name.starts_with(curve::PREFIX) {
+            // read once by `curve::load_ball_path`
         } else {
             commands.entity(entity).insert(Deco);
         }
@@ -117,14 +155,17 @@ fn tag_entity(mut commands: Commands, query: Query<(Entity, &Name)>) {
 fn setup_complation(
     mut commands: Commands,
     mut state: ResMut<State<AppState>>,
-    mut query: Query<(Entity, &mut Transform), With<MainCamera>>,
+    mut query: Query<(Entity, &mut Transform, Option<&SpawnedCamera>), With<MainCamera>>,
 ) {
-    {
-        let (camera, mut transform) = query.single_mut().unwrap();
-        let point = Vec3::new(0.0, 0.0, 10.0);
-        *transform = Transform::from_translation(point).looking_at(Vec3::ZERO, Vec3::Y);
+    if let Ok((camera, mut transform, spawned)) = query.single_mut() {
+        if spawned.is_some() {
+            let point = Vec3::new(0.0, 0.0, 10.0);
+            *transform = Transform::from_translation(point).looking_at(Vec3::ZERO, Vec3::Y);
+        }
         commands.entity(camera)
             .insert_bundle(PickingCameraBundle::default());
+    } else {
+        warn!("no MainCamera, picking is off");
     }
 
     state.set(AppState::InGame).unwrap();
@@ -136,15 +177,22 @@ fn setup_ui(mut commands: Commands) {
 
 fn debug(
     key: Res<Input<KeyCode>>,
+    input_mode: Res<InputMode>,
+    mut settings: ResMut<Settings>,
     mut debug: ResMut<Debug>,
     mut visible_query: Query<(&mut Visible, &DebugVisible)>,
+    added_query: Query<(), Added<DebugVisible>>,
 ) {
-    if key.just_pressed(KeyCode::X) {
-        let (new, enter_debug) = match *debug {
-            Debug::On  => (Debug::Off, false),
-            Debug::Off => (Debug::On,  true),
-        };
+    if input_mode.keyboard() && key.just_pressed(KeyCode::X) {
+        settings.debug = !settings.debug;
+        settings.save();
+    }
 
+    let enter_debug = settings.debug;
+    let is_debug = matches!(*debug, Debug::On);
+    // entities spawned later start in the non-debug look
+    let is_added = enter_debug && added_query.iter().next().is_some();
+    if enter_debug != is_debug || is_added {
         for (mut visible, debug_visible) in visible_query.iter_mut() {
             visible.is_visible = match debug_visible {
                 DebugVisible::Yes => enter_debug,
@@ -152,6 +200,6 @@ fn debug(
             };
         }
 
-        *debug = new;
+        *debug = if enter_debug { Debug::On } else { Debug::Off };
     }
 }
@@ -0,0 +1,49 @@
+use std::convert::TryFrom;
+
+use bevy::prelude::*;
+
+use crate::component::CubeDescriptor;
+use crate::util::bezier::{ControlPoint, Path};
+
+/// Names of the empties tracing the right loop: `path.cp1` to `path.cp6` at
+/// the control point origins under `cube`, each with `.backward` and
+/// `.forward` children at its bezier handles. Only their translations are
+/// read, so `cube` and the `path.cp*` empties must not be rotated or scaled.
+pub const PREFIX: &str = "path.";
+const N_POINTS: usize = 6;
+
+/// Replaces the built in loops with the curve authored in the model, if the
+/// scene has one. The curve is flat, `z` is dropped: the rotate handle moves
+/// the balls between the up and down loops.
+pub(super) fn load_ball_path(
+    mut cube: ResMut<CubeDescriptor>,
+    query: Query<(&Name, &Transform)>,
+) {
+    let find = |name: String| {
+        query.iter()
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, transform)| transform.translation * Vec3::new(1.0, 1.0, 0.0))
+    };
+    let points = (1..=N_POINTS)
+        .map(|i| {
+            let name = format!("{}cp{}", PREFIX, i);
+            let origin = find(name.clone())?;
+            Some(ControlPoint {
+                backward: origin + find(format!("{}.backward", name))?,
+                origin,
+                forward: origin + find(format!("{}.forward", name))?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match points.iter().filter(|cp| cp.is_some()).count() {
+        0 => info!("no path nodes in the scene, using the built in loops"),
+        N_POINTS => {
+            let cp = points.into_iter().flatten().collect::<Vec<_>>();
+            if let Ok(cp) = <[ControlPoint; N_POINTS]>::try_from(cp) {
+                cube.set_ball_path(Path { cp });
+            }
+        }
+        n => warn!("{} of {} path nodes in the scene, using the built in loops", n, N_POINTS),
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use bevy::asset::{Asset, LoadState};
+
+use crate::AppState;
+
+const LOAD_TIMEOUT_SECONDS: f32 = 30.0;
+const REQUIRED_NODES: [&str; 3] = ["cube", "block.slide", "block.rotate"];
+
+/// The scene `AppState::Load` waits for, `None` for a procedural scene.
+pub struct LoadingScene {
+    pub handle: Option<Handle<Scene>>,
+    /// Parent of every node of the scene, despawned for a retry.
+    pub root: Entity,
+    pub timer: Timer,
+    // frames the scene asset has existed, it is spawned one frame later
+    present_frames: usize,
+}
+
+impl LoadingScene {
+    pub fn new(handle: Option<Handle<Scene>>, root: Entity) -> Self {
+        LoadingScene {
+            handle,
+            root,
+            timer: Timer::from_seconds(LOAD_TIMEOUT_SECONDS, false),
+            present_frames: 0,
+        }
+    }
+}
+
+/// Assets shown on the loading screen.
+#[derive(Default)]
+pub struct LoadingAssets {
+    entries: Vec<(String, HandleUntyped)>,
+}
+
+impl LoadingAssets {
+    pub fn track<T: Asset>(&mut self, label: &str, handle: &Handle<T>) {
+        let handle = handle.clone_untyped();
+        if self.entries.iter().all(|(_, h)| h.id != handle.id) {
+            self.entries.push((label.to_string(), handle));
+        }
+    }
+
+    pub fn iter_state<'a>(
+        &'a self,
+        asset_server: &'a AssetServer,
+    ) -> impl Iterator<Item=(&'a str, LoadState)> + 'a {
+        self.entries.iter()
+            .map(move |(label, handle)| (label.as_str(), asset_server.get_load_state(handle)))
+    }
+
+    pub fn progress(&self, asset_server: &AssetServer) -> f32 {
+        if self.entries.is_empty() {
+            return 1.0;
+        }
+        let loaded = self.iter_state(asset_server)
+            .filter(|(_, state)| *state == LoadState::Loaded)
+            .count();
+        loaded as f32 / self.entries.len() as f32
+    }
+}
+
+/// Why the game went to `AppState::Error`.
+pub struct SceneError {
+    pub messages: Vec<String>,
+}
+
+struct LoadingScreen;
+struct ProgressBar;
+struct ProgressText;
+struct ErrorScreen;
+struct RetryButton;
+
+pub(super) fn load_complation(
+    mut commands: Commands,
+    mut state: ResMut<State<AppState>>,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    scenes: Res<Assets<Scene>>,
+    mut loading: ResMut<LoadingScene>,
+    node_query: Query<(&Name, Option<&Children>)>,
+    mesh_query: Query<&Handle<Mesh>>,
+) {
+    let mut fail = |messages| {
+        commands.insert_resource(SceneError { messages });
+        state.set(AppState::Error).unwrap();
+    };
+
+    let is_present = match &loading.handle {
+        // spawned right away in `setup_scene`
+        None => true,
+        Some(handle) => {
+            if asset_server.get_load_state(handle) == LoadState::Failed {
+                fail(vec!["failed to load the scene asset".to_string()]);
+                return;
+            }
+            scenes.get(handle).is_some()
+        }
+    };
+    if is_present {
+        loading.present_frames += 1;
+    }
+
+    if loading.present_frames < 2 {
+        if loading.timer.tick(time.delta()).finished() {
+            fail(vec![format!("scene did not load within {} seconds", LOAD_TIMEOUT_SECONDS)]);
+        }
+        return;
+    }
+
+    let messages = validate(&node_query, &mesh_query);
+    if messages.is_empty() {
+        state.set(AppState::Setup).unwrap();
+    } else {
+        fail(messages);
+    }
+}
+
+/// Reports every node `setup_cube` needs but the scene lacks.
+fn validate(
+    node_query: &Query<(&Name, Option<&Children>)>,
+    mesh_query: &Query<&Handle<Mesh>>,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    for name in REQUIRED_NODES.iter() {
+        let nodes = node_query.iter()
+            .filter(|(n, _)| n.as_str() == *name)
+            .collect::<Vec<_>>();
+        match nodes.as_slice() {
+            [] => messages.push(format!("missing node `{}`", name)),
+            [(_, children)] => {
+                let has_mesh = children.map_or(false, |children| {
+                    children.iter().any(|c| mesh_query.get(*c).is_ok())
+                });
+                if !has_mesh {
+                    messages.push(format!("node `{}` has no mesh", name));
+                }
+            }
+            _ => messages.push(format!("{} nodes named `{}`, expected one", nodes.len(), name)),
+        }
+    }
+    messages
+}
+
+pub(super) fn setup_loading_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    let font = asset_server.load("FiraMono-Medium.ttf");
+    loading.track("font", &font);
+    let style = TextStyle {
+        font,
+        font_size: 30.0,
+        color: Color::rgb(0.0, 1.0, 1.0),
+    };
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        },
+        material: materials.add(Color::NONE.into()),
+        ..Default::default()
+    })
+    .insert(LoadingScreen)
+    .with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            text: Text::with_section("Loading", style.clone(), Default::default()),
+            ..Default::default()
+        }).insert(ProgressText);
+
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(400.0), Val::Px(16.0)),
+                margin: Rect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                    ..Default::default()
+                },
+                material: materials.add(Color::rgb(0.0, 0.8, 0.8).into()),
+                ..Default::default()
+            }).insert(ProgressBar);
+        });
+    });
+}
+
+pub(super) fn loading_progress(
+    asset_server: Res<AssetServer>,
+    assets: Res<LoadingAssets>,
+    mut bar_query: Query<&mut Style, With<ProgressBar>>,
+    mut text_query: Query<&mut Text, With<ProgressText>>,
+) {
+    let progress = assets.progress(&asset_server);
+    for mut style in bar_query.iter_mut() {
+        style.size.width = Val::Percent(progress * 100.0);
+    }
+
+    let pending = assets.iter_state(&asset_server)
+        .filter(|(_, state)| *state != LoadState::Loaded)
+        .map(|(label, _)| label)
+        .collect::<Vec<_>>();
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = if pending.is_empty() {
+            "Loading".to_string()
+        } else {
+            format!("Loading {}", pending.join(", "))
+        };
+    }
+}
+
+pub(super) fn clear_loading_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn setup_error_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    error: Res<SceneError>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let style = TextStyle {
+        font: asset_server.load("FiraMono-Medium.ttf"),
+        font_size: 30.0,
+        color: Color::rgb(1.0, 0.4, 0.3),
+    };
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        },
+        material: materials.add(Color::NONE.into()),
+        ..Default::default()
+    })
+    .insert(ErrorScreen)
+    .with_children(|parent| {
+        let lines = std::iter::once("Failed to load the puzzle:".to_string())
+            .chain(error.messages.iter().cloned());
+        for line in lines {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                text: Text::with_section(line, style.clone(), Default::default()),
+                ..Default::default()
+            });
+        }
+
+        parent.spawn_bundle(ButtonBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(12.0)),
+                padding: Rect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgb(0.2, 0.2, 0.2).into()),
+            ..Default::default()
+        })
+        .insert(RetryButton)
+        .with_children(|parent| {
+            let style = TextStyle {
+                color: Color::WHITE,
+                ..style.clone()
+            };
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("Retry", style, Default::default()),
+                ..Default::default()
+            });
+        });
+    });
+}
+
+pub(super) fn retry(
+    mut state: ResMut<State<AppState>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>,
+) {
+    if query.iter().any(|interaction| *interaction == Interaction::Clicked) {
+        state.set(AppState::Load).unwrap();
+    }
+}
+
+/// Drops the error screen and whatever part of the scene got spawned, the
+/// next `AppState::Load` spawns it again.
+pub(super) fn clear_error_screen(
+    mut commands: Commands,
+    loading: Res<LoadingScene>,
+    screen_query: Query<Entity, With<ErrorScreen>>,
+) {
+    for entity in screen_query.iter().chain(std::iter::once(loading.root)) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
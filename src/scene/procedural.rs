@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+use crate::component::*;
+
+const GROOVE_SAMPLES: usize = 96;
+const GROOVE_SIDES: usize = 6;
+const GROOVE_RADIUS: f32 = 0.04;
+
+/// Spawns the cube body, grooves and both blocks with the node names the
+/// glTF scene uses, so `tag_entity` and `setup_cube` treat them the same.
+pub(super) fn spawn_cube(
+    commands: &mut Commands,
+    cube: &CubeDescriptor,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> Entity {
+    let mut add_material = |base_color, roughness| {
+        materials.add(StandardMaterial {
+            base_color,
+            roughness,
+            reflectance: 0.3,
+            ..Default::default()
+        })
+    };
+    let body_material = add_material(Color::rgb(0.8, 0.8, 0.75), 0.6);
+    let groove_material = add_material(Color::rgb(0.2, 0.2, 0.2), 0.8);
+    let slide_material = add_material(Color::rgb(0.45, 0.5, 0.6), 0.4);
+    let rotate_material = add_material(Color::rgb(0.6, 0.55, 0.45), 0.4);
+
+    let body = meshes.add(Mesh::from(cube.body_box()));
+    let groove = meshes.add(groove_mesh(cube));
+    let slide = meshes.add(Mesh::from(cube.slide_block_box()));
+    let rotate = meshes.add(Mesh::from(cube.rotate_block_box()));
+
+    let node = |name: &str| {
+        (Name::new(name.to_string()), Transform::identity(), GlobalTransform::identity())
+    };
+    let pbr = |mesh, material| {
+        PbrBundle {
+            mesh,
+            material,
+            ..Default::default()
+        }
+    };
+
+    commands.spawn_bundle(node("cube")).with_children(|parent| {
+        parent.spawn_bundle(pbr(body, body_material));
+        parent.spawn_bundle(node("cube.groove")).with_children(|parent| {
+            parent.spawn_bundle(pbr(groove, groove_material));
+        });
+        parent.spawn_bundle(node("block.slide")).with_children(|parent| {
+            parent.spawn_bundle(pbr(slide, slide_material));
+        });
+        parent.spawn_bundle(node("block.rotate")).with_children(|parent| {
+            parent.spawn_bundle(pbr(rotate, rotate_material));
+        });
+    }).id()
+}
+
+/// Two rails along each ball loop on both faces, following the ball centers
+/// given by the descriptor.
+fn groove_mesh(cube: &CubeDescriptor) -> Mesh {
+    let mut builder = MeshBuilder::default();
+    let loops = [
+        (SlideHandle::left(), RotateHandle::up()),
+        (SlideHandle::right(), RotateHandle::up()),
+        (SlideHandle::left(), RotateHandle::down()),
+        (SlideHandle::right(), RotateHandle::down()),
+    ];
+    for (slide, rotate) in std::array::IntoIter::new(loops) {
+        let centers = (0..GROOVE_SAMPLES)
+            .map(|i| {
+                let path = PathHandle::new(i as f32 / GROOVE_SAMPLES as f32);
+                let bundle = BallHandleBundle { path, slide, rotate };
+                cube.get_ball_transform(&bundle).translation
+            })
+            .collect::<Vec<_>>();
+
+        for side in [-1.0f32, 1.0].iter() {
+            let rail = centers.iter().enumerate()
+                .map(|(i, center)| {
+                    let prev = centers[(i + GROOVE_SAMPLES - 1) % GROOVE_SAMPLES];
+                    let next = centers[(i + 1) % GROOVE_SAMPLES];
+                    let normal = Vec3::Z.cross(next - prev).normalize();
+                    let floor = cube.floor_z().copysign(center.z);
+                    let point = *center + normal * cube.ball_radians() * 0.8 * *side;
+                    Vec3::new(point.x, point.y, floor)
+                })
+                .collect::<Vec<_>>();
+            builder.add_closed_tube(&rail, GROOVE_RADIUS);
+        }
+    }
+    builder.build()
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    /// A tube around a closed polyline lying in a plane of constant z.
+    fn add_closed_tube(&mut self, points: &[Vec3], radius: f32) {
+        let n = points.len();
+        let base = self.positions.len() as u32;
+        for (i, point) in points.iter().enumerate() {
+            let tangent = points[(i + 1) % n] - points[(i + n - 1) % n];
+            let side = Vec3::Z.cross(tangent).normalize();
+            for j in 0..GROOVE_SIDES {
+                let angle = j as f32 / GROOVE_SIDES as f32 * std::f32::consts::PI * 2.0;
+                let normal = side * angle.cos() + Vec3::Z * angle.sin();
+                let position = *point + normal * radius;
+                self.positions.push(position.into());
+                self.normals.push(normal.into());
+                self.uvs.push([i as f32 / n as f32, j as f32 / GROOVE_SIDES as f32]);
+            }
+        }
+
+        let sides = GROOVE_SIDES as u32;
+        for i in 0..n as u32 {
+            let ring = base + i * sides;
+            let next_ring = base + ((i + 1) % n as u32) * sides;
+            for j in 0..sides {
+                let k = (j + 1) % sides;
+                self.indices.extend_from_slice(&[
+                    ring + j, next_ring + j, next_ring + k,
+                    ring + j, next_ring + k, ring + k,
+                ]);
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
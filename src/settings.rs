@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::component::*;
+
+mod menu;
+
+/// User options, edited in the settings menu and kept between runs.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub drag_sensitivity: f32,
+    pub orbit_sensitivity: f32,
+    pub snap_seconds: f32,
+    pub inertia: bool,
+    /// Read once at startup, changes apply on restart.
+    pub msaa_samples: u32,
+    pub debug: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            drag_sensitivity: 1.0,
+            orbit_sensitivity: 1.0,
+            snap_seconds: 0.2,
+            inertia: true,
+            msaa_samples: 4,
+            debug: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::read()
+            .and_then(|text| ron::from_str(&text).map_err(|e| warn!("bad settings: {}", e)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, Default::default()) {
+            Ok(text) => storage::write(&text),
+            Err(e) => warn!("cannot save settings: {}", e),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::path::PathBuf;
+
+    use bevy::log::warn;
+
+    /// `settings.ron` in the platform config dir, like
+    /// `~/.config/magic_bean` on Linux.
+    fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("magic_bean").join("settings.ron"))
+    }
+
+    pub fn read() -> Option<String> {
+        std::fs::read_to_string(path()?).ok()
+    }
+
+    pub fn write(text: &str) {
+        let path = match path() {
+            Some(path) => path,
+            None => {
+                warn!("no config dir to save settings in");
+                return;
+            }
+        };
+        let written = match path.parent() {
+            Some(dir) => std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, text)),
+            None => std::fs::write(&path, text),
+        };
+        if let Err(e) = written {
+            warn!("cannot save settings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    const KEY: &str = "magic_bean.settings";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn read() -> Option<String> {
+        local_storage()?.get_item(KEY).ok()?
+    }
+
+    pub fn write(text: &str) {
+        if let Some(storage) = local_storage() {
+            let _ = storage.set_item(KEY, text);
+        }
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.world().get_resource::<Settings>().is_none() {
+            app.insert_resource(Settings::load());
+        }
+
+        app
+            .add_system(apply_settings.system())
+            .add_system_set(SystemSet::on_enter(AppState::Menu)
+                .with_system(menu::setup_menu.system())
+            )
+            .add_system_set(SystemSet::on_update(AppState::Menu)
+                .with_system(menu::menu_buttons.system())
+                .with_system(menu::menu_values.system())
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Menu)
+                .with_system(menu::clear_menu.system())
+            );
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut drag_config: ResMut<DragConfig>,
+    mut view_config: ResMut<ViewConfig>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    drag_config.sensitivity = settings.drag_sensitivity;
+    drag_config.snap_seconds = settings.snap_seconds;
+    drag_config.inertia = settings.inertia;
+    view_config.orbit_sensitivity = settings.orbit_sensitivity;
+}
@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use super::Settings;
+
+struct MenuScreen;
+
+#[derive(Clone, Copy)]
+enum Setting {
+    DragSensitivity,
+    OrbitSensitivity,
+    SnapSeconds,
+    Inertia,
+    Msaa,
+    Debug,
+}
+
+impl Setting {
+    const ALL: [Setting; 6] = [
+        Setting::DragSensitivity,
+        Setting::OrbitSensitivity,
+        Setting::SnapSeconds,
+        Setting::Inertia,
+        Setting::Msaa,
+        Setting::Debug,
+    ];
+
+    fn label(&self) -> &'static str {
+        use Setting::*;
+        match self {
+            DragSensitivity => "Drag sensitivity",
+            OrbitSensitivity => "Orbit sensitivity",
+            SnapSeconds => "Snap seconds",
+            Inertia => "Inertia",
+            Msaa => "MSAA (restart)",
+            Debug => "Debug view",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        fn on_off(on: bool) -> String {
+            if on { "on" } else { "off" }.to_string()
+        }
+
+        use Setting::*;
+        match self {
+            DragSensitivity => format!("{:.1}", settings.drag_sensitivity),
+            OrbitSensitivity => format!("{:.1}", settings.orbit_sensitivity),
+            SnapSeconds => format!("{:.2}", settings.snap_seconds),
+            Inertia => on_off(settings.inertia),
+            Msaa => format!("{}x", settings.msaa_samples),
+            Debug => on_off(settings.debug),
+        }
+    }
+
+    fn adjust(&self, settings: &mut Settings, direction: f32) {
+        use Setting::*;
+        match self {
+            DragSensitivity => {
+                settings.drag_sensitivity = (settings.drag_sensitivity + 0.1 * direction).clamp(0.2, 3.0);
+            }
+            OrbitSensitivity => {
+                settings.orbit_sensitivity = (settings.orbit_sensitivity + 0.1 * direction).clamp(0.2, 3.0);
+            }
+            SnapSeconds => {
+                settings.snap_seconds = (settings.snap_seconds + 0.05 * direction).clamp(0.05, 1.0);
+            }
+            Inertia => settings.inertia = !settings.inertia,
+            Msaa => settings.msaa_samples = if settings.msaa_samples > 1 { 1 } else { 4 },
+            Debug => settings.debug = !settings.debug,
+        }
+    }
+}
+
+enum MenuButton {
+    Adjust(Setting, f32),
+    Back,
+}
+
+struct SettingValue(Setting);
+
+pub(super) fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let style = TextStyle {
+        font: asset_server.load("FiraMono-Medium.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let transparent = materials.add(Color::NONE.into());
+    let button = materials.add(Color::rgb(0.2, 0.2, 0.2).into());
+
+    let spawn_button = |parent: &mut ChildBuilder, text: &str, action: MenuButton| {
+        parent.spawn_bundle(ButtonBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(4.0)),
+                padding: Rect::all(Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            material: button.clone(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(text, style.clone(), Default::default()),
+                ..Default::default()
+            });
+        });
+    };
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        },
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.7).into()),
+        ..Default::default()
+    })
+    .insert(MenuScreen)
+    .with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(12.0)),
+                ..Default::default()
+            },
+            text: Text::with_section("Settings", style.clone(), Default::default()),
+            ..Default::default()
+        });
+
+        for setting in Setting::ALL.iter() {
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: transparent.clone(),
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(280.0), Val::Auto),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(setting.label(), style.clone(), Default::default()),
+                    ..Default::default()
+                });
+                spawn_button(parent, "-", MenuButton::Adjust(*setting, -1.0));
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(80.0), Val::Auto),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(setting.value(&settings), style.clone(), Default::default()),
+                    ..Default::default()
+                })
+                .insert(SettingValue(*setting));
+                spawn_button(parent, "+", MenuButton::Adjust(*setting, 1.0));
+            });
+        }
+
+        spawn_button(parent, "Back", MenuButton::Back);
+    });
+}
+
+pub(super) fn menu_buttons(
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<State<AppState>>,
+    query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            MenuButton::Adjust(setting, direction) => setting.adjust(&mut settings, *direction),
+            MenuButton::Back => state.pop().unwrap(),
+        }
+    }
+}
+
+pub(super) fn menu_values(
+    settings: Res<Settings>,
+    mut query: Query<(&mut Text, &SettingValue)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, value) in query.iter_mut() {
+        text.sections[0].value = value.0.value(&settings);
+    }
+}
+
+pub(super) fn clear_menu(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    query: Query<Entity, With<MenuScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    settings.save();
+}
@@ -0,0 +1,451 @@
+//! Solves positions for the colors mode: iterative deepening A* for a
+//! shortest solution, then a greedy search over short move sequences for a
+//! longer one when that runs out of time or depth. Plain data like `puzzle`,
+//! shared by the `solver` binary and the tests.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::puzzle::{color, home, Move, Puzzle, SolveMode};
+use crate::puzzle::{LOOP_LEN, N_BALLS, N_LOOPS, ROTATE_WINDOW, WINDOW_LEN};
+
+/// Nodes between two looks at the clock.
+const CLOCK_NODES: u64 = 1 << 14;
+/// Slots of the four loops, a place is a slot and the side of the blocks.
+const N_SLOTS: usize = LOOP_LEN * N_LOOPS;
+const N_PLACES: usize = N_SLOTS * 2;
+/// Share of the time limit the optimal search gets before the fallback.
+const OPTIMAL_SHARE: f64 = 0.5;
+/// Steps of the fallback's macros, a turn by any number of slots is one.
+const MACRO_STEPS: usize = 5;
+/// Most balls a macro of the fallback may move.
+const MACRO_BALLS: usize = 6;
+/// Most slots a macro turns a loop by at once, the setups do the rest.
+const MACRO_TURN: i32 = 5;
+/// Draws for a sideways macro before the fallback gives up.
+const SIDEWAYS_DRAWS: usize = 1 << 16;
+/// Most macros the fallback brings before it gives up, whatever the time
+/// limit, a scramble takes a few dozen.
+const FALLBACK_MACROS: usize = 1 << 8;
+
+pub struct Limits {
+    pub time_limit: Duration,
+    /// Longest solution the optimal search looks for.
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            time_limit: Duration::from_secs(10),
+            max_depth: 30,
+        }
+    }
+}
+
+pub struct Report {
+    pub solution: Option<Vec<Move>>,
+    /// Moves the position is at least away from solved.
+    pub lower_bound: usize,
+    pub is_optimal: bool,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+pub fn solve(start: &Puzzle, limits: &Limits) -> Report {
+    let started = Instant::now();
+    let distances = Distances::new();
+    let mut search = Search {
+        distances: &distances,
+        deadline: started.checked_add(limits.time_limit.mul_f64(OPTIMAL_SHARE)),
+        nodes: 0,
+        is_timed_out: false,
+        path: Vec::new(),
+    };
+
+    let mut puzzle = start.clone();
+    let mut bound = distances.estimate(start);
+    let mut solution = None;
+    while bound <= limits.max_depth {
+        match search.dfs(&mut puzzle, bound) {
+            Step::Found => {
+                solution = Some(search.path.clone());
+                break;
+            }
+            Step::Over(_) if search.is_timed_out => break,
+            Step::Over(Some(next)) => bound = next,
+            Step::Over(None) => break,
+        }
+    }
+    let is_optimal = solution.is_some();
+    let mut nodes = search.nodes;
+
+    if solution.is_none() {
+        let (fallback, fallback_nodes) = fallback(start, started.checked_add(limits.time_limit));
+        solution = fallback;
+        nodes += fallback_nodes;
+    }
+
+    Report {
+        lower_bound: if is_optimal { solution.as_ref().map_or(bound, Vec::len) } else { bound },
+        solution,
+        is_optimal,
+        nodes,
+        elapsed: started.elapsed(),
+    }
+}
+
+fn is_past(deadline: Option<Instant>) -> bool {
+    deadline.map_or(false, |deadline| Instant::now() > deadline)
+}
+
+/// Index of slot `index` of loop `group` with the blocks on the left or not.
+fn place(group: usize, index: usize, is_block_left: bool) -> usize {
+    let side = if is_block_left { 0 } else { N_SLOTS };
+    side + group * LOOP_LEN + index
+}
+
+/// Moves a single ball needs from each place to a slot of each color. A
+/// move takes every ball at most one step, so no position is closer than
+/// its farthest ball.
+struct Distances {
+    moves: Vec<[u8; N_PLACES]>,
+}
+
+impl Distances {
+    fn new() -> Self {
+        let moves = (0..N_LOOPS)
+            .map(|target| {
+                let mut moves = [u8::MAX; N_PLACES];
+                let mut queue = VecDeque::new();
+                for group in 0..N_LOOPS {
+                    for index in 0..LOOP_LEN {
+                        if home(group, index).map_or(false, |ball| color(ball) == target) {
+                            let p = place(group, index, true);
+                            moves[p] = 0;
+                            queue.push_back((group, index, true));
+                        }
+                    }
+                }
+                // every move has an inverse, so the steps back are the same
+                while let Some((group, index, is_block_left)) = queue.pop_front() {
+                    let next = moves[place(group, index, is_block_left)] + 1;
+                    for (g, i, left) in Distances::steps(group, index, is_block_left) {
+                        let p = place(g, i, left);
+                        if moves[p] == u8::MAX {
+                            moves[p] = next;
+                            queue.push_back((g, i, left));
+                        }
+                    }
+                }
+                moves
+            })
+            .collect();
+        Distances { moves }
+    }
+
+    /// Where one move can take a ball from `index` of `group`.
+    fn steps(group: usize, index: usize, is_block_left: bool) -> Vec<(usize, usize, bool)> {
+        let is_under_block = (group % 2 == 0) == is_block_left;
+        let mut steps = Vec::with_capacity(4);
+        if is_under_block {
+            steps.push((group, (index + 1) % LOOP_LEN, is_block_left));
+            steps.push((group, (index + LOOP_LEN - 1) % LOOP_LEN, is_block_left));
+            if ROTATE_WINDOW.contains(&index) {
+                steps.push((group ^ 2, index, is_block_left));
+            }
+        }
+        // the window goes with the blocks, the rest stays
+        let slid = if is_under_block && index < WINDOW_LEN { group ^ 1 } else { group };
+        steps.push((slid, index, !is_block_left));
+        steps
+    }
+
+    fn ball(&self, puzzle: &Puzzle, ball: u8, group: usize, index: usize) -> usize {
+        self.moves[color(ball)][place(group, index, puzzle.is_block_left())] as usize
+    }
+
+    /// Lower bound of the moves left: the farthest ball, and the balls off
+    /// their color over the most a turn can move.
+    fn estimate(&self, puzzle: &Puzzle) -> usize {
+        let mut farthest = 0;
+        let mut wrong = 0;
+        for (ball, group, index) in puzzle.places() {
+            farthest = farthest.max(self.ball(puzzle, ball, group, index));
+            if home(group, index).map_or(true, |home| color(home) != color(ball)) {
+                wrong += 1;
+            }
+        }
+        farthest.max((wrong + LOOP_LEN - 1) / LOOP_LEN)
+    }
+}
+
+/// Whether `m` after `path` can only repeat a shorter or an equal solution.
+fn is_redundant(path: &[Move], m: Move) -> bool {
+    let last = match path.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    if last == m.inverse() {
+        return true;
+    }
+    match (last, m) {
+        // turns of different loops commute, keep them in group order
+        (Move::Turn { group: a, .. }, Move::Turn { group: b, .. }) if a != b => b < a,
+        // half a loop turns either way, only forward turns go all the way
+        (Move::Turn { forward, .. }, _) if last == m => {
+            let run = path.iter().rev().take_while(|x| **x == m).count();
+            run >= if forward { LOOP_LEN / 2 } else { LOOP_LEN / 2 - 1 }
+        }
+        _ => false,
+    }
+}
+
+struct Search<'a> {
+    distances: &'a Distances,
+    /// `None` past the clock's range.
+    deadline: Option<Instant>,
+    nodes: u64,
+    is_timed_out: bool,
+    path: Vec<Move>,
+}
+
+enum Step {
+    Found,
+    /// Smallest estimate over the bound, `None` if no move was left.
+    Over(Option<usize>),
+}
+
+impl Search<'_> {
+    fn dfs(&mut self, puzzle: &mut Puzzle, bound: usize) -> Step {
+        self.nodes += 1;
+        if self.nodes % CLOCK_NODES == 0 && is_past(self.deadline) {
+            self.is_timed_out = true;
+        }
+        if self.is_timed_out {
+            return Step::Over(None);
+        }
+
+        let f = self.path.len() + self.distances.estimate(puzzle);
+        if f > bound {
+            return Step::Over(Some(f));
+        }
+        if puzzle.is_solved(SolveMode::Colors) {
+            return Step::Found;
+        }
+
+        let mut next = None;
+        for m in puzzle.legal_moves() {
+            if is_redundant(&self.path, m) {
+                continue;
+            }
+            puzzle.apply(m).unwrap();
+            self.path.push(m);
+            match self.dfs(puzzle, bound) {
+                Step::Found => return Step::Found,
+                Step::Over(Some(f)) => next = Some(next.map_or(f, |next: usize| next.min(f))),
+                Step::Over(None) => {}
+            }
+            self.path.pop();
+            puzzle.apply(m.inverse()).unwrap();
+        }
+        Step::Over(next)
+    }
+}
+
+/// Moves that start and end with the blocks on the left and move few balls.
+struct Macro {
+    moves: Vec<Move>,
+    /// Every slot a ball leaves, with the slot it ends in.
+    cycles: Vec<(usize, usize)>,
+}
+
+/// Moves that bring the slots of a macro where it is needed, and back after.
+struct Setup {
+    moves: Vec<Move>,
+    /// The slot each ball comes from, by the slot it ends in.
+    from: Vec<usize>,
+}
+
+fn slot(group: usize, index: usize) -> usize {
+    group * LOOP_LEN + index
+}
+
+fn turns(group: usize, slots: i32) -> Vec<Move> {
+    let m = Move::Turn { group, forward: slots > 0 };
+    vec![m; slots.unsigned_abs() as usize]
+}
+
+/// Every slot `puzzle` moved a ball from the solved one, numbered balls.
+fn cycles(puzzle: &Puzzle, home_slots: &[usize]) -> Vec<(usize, usize)> {
+    puzzle.places()
+        .map(|(ball, group, index)| (home_slots[ball as usize], slot(group, index)))
+        .filter(|(from, to)| from != to)
+        .collect()
+}
+
+/// The shortest sequences of up to `MACRO_STEPS` turns, slides and rotates
+/// that move no more than `MACRO_BALLS` balls.
+fn macros(home_slots: &[usize]) -> Vec<Macro> {
+    let solved = Puzzle::solved();
+    let mut shortest = HashMap::new();
+    shortest.insert(solved.clone(), 0);
+    // a turn of the loop turned last is part of the same step
+    let mut layer = vec![(solved, Vec::new(), None)];
+    let mut macros = Vec::new();
+    for steps_left in (0..MACRO_STEPS).rev() {
+        let mut next_layer = Vec::new();
+        for (puzzle, moves, last_group) in layer.iter() {
+            let mut steps = vec![
+                (vec![Move::Slide { right: puzzle.is_block_left() }], None),
+                (vec![Move::Rotate], None),
+            ];
+            for &group in puzzle.block_groups().iter() {
+                if Some(group) != *last_group {
+                    steps.extend((-MACRO_TURN..=MACRO_TURN).filter(|n| *n != 0).map(|n| (turns(group, n), Some(group))));
+                }
+            }
+            for (step, group) in steps {
+                let mut next = puzzle.clone();
+                for m in step.iter() {
+                    next.apply(*m).unwrap();
+                }
+                // the last step is only worth keeping as a macro
+                if steps_left == 0 && !next.is_block_left() {
+                    continue;
+                }
+                let mut next_moves = moves.clone();
+                next_moves.extend(step);
+                if shortest.get(&next).map_or(false, |len| *len <= next_moves.len()) {
+                    continue;
+                }
+                shortest.insert(next.clone(), next_moves.len());
+                if next.is_block_left() {
+                    let cycles = cycles(&next, home_slots);
+                    if !cycles.is_empty() && cycles.len() <= MACRO_BALLS {
+                        macros.push(Macro { moves: next_moves.clone(), cycles });
+                    }
+                }
+                next_layer.push((next, next_moves, group));
+            }
+        }
+        layer = next_layer;
+    }
+    macros
+}
+
+/// Every turn of the two loops under the blocks on either side.
+fn setups(home_slots: &[usize]) -> Vec<Setup> {
+    let half = (LOOP_LEN / 2) as i32;
+    let mut setups = Vec::new();
+    for &is_right in [false, true].iter() {
+        let [first, second] = if is_right { [1, 3] } else { [0, 2] };
+        for i in 1 - half..=half {
+            for j in 1 - half..=half {
+                let mut moves = turns(first, i);
+                moves.extend(turns(second, j));
+                if is_right {
+                    moves.insert(0, Move::Slide { right: true });
+                    moves.push(Move::Slide { right: false });
+                }
+                let mut puzzle = Puzzle::solved();
+                for m in moves.iter() {
+                    puzzle.apply(*m).unwrap();
+                }
+                let mut from = vec![0; N_SLOTS];
+                for (ball, group, index) in puzzle.places() {
+                    from[slot(group, index)] = home_slots[ball as usize];
+                }
+                setups.push(Setup { moves, from });
+            }
+        }
+    }
+    setups
+}
+
+/// Greedy search for any solution: brings macros where they put the most
+/// balls on their color for their moves, or when none does, one that keeps
+/// the count, picked at random. Gives up past the deadline or after
+/// `FALLBACK_MACROS` macros.
+fn fallback(start: &Puzzle, deadline: Option<Instant>) -> (Option<Vec<Move>>, u64) {
+    let mut home_slots = vec![0; N_BALLS];
+    for (ball, group, index) in Puzzle::solved().places() {
+        home_slots[ball as usize] = slot(group, index);
+    }
+    let macros = macros(&home_slots);
+    let setups = setups(&home_slots);
+    let targets = (0..N_SLOTS)
+        .map(|s| home(s / LOOP_LEN, s % LOOP_LEN).map(color))
+        .collect::<Vec<_>>();
+    let rng = fastrand::Rng::with_seed(0);
+
+    let mut puzzle = start.clone();
+    let mut path = Vec::new();
+    if !puzzle.is_block_left() {
+        puzzle.apply(Move::Slide { right: false }).unwrap();
+        path.push(Move::Slide { right: false });
+    }
+    let mut nodes = 0;
+
+    for _ in 0..FALLBACK_MACROS {
+        if puzzle.is_solved(SolveMode::Colors) {
+            return (Some(path), nodes);
+        }
+        if is_past(deadline) {
+            return (None, nodes);
+        }
+        let colors = (0..N_SLOTS)
+            .map(|s| puzzle.get(s / LOOP_LEN, s % LOOP_LEN).map(color))
+            .collect::<Vec<_>>();
+        // balls the macro puts off their color, less the ones it puts on
+        let gain = |setup: &Setup, m: &Macro| -> (i32, bool) {
+            let mut gain = 0;
+            let mut is_changing = false;
+            for (from, to) in m.cycles.iter() {
+                let (from, to) = (setup.from[*from], setup.from[*to]);
+                gain += (colors[to] != targets[to]) as i32 - (colors[from] != targets[to]) as i32;
+                is_changing |= colors[from] != colors[to];
+            }
+            (gain, is_changing)
+        };
+
+        let mut best = None;
+        let mut best_rate = 0.0;
+        for setup in setups.iter() {
+            for m in macros.iter() {
+                nodes += 1;
+                let (gain, _) = gain(setup, m);
+                let rate = gain as f32 / (m.moves.len() + 2 * setup.moves.len()) as f32;
+                if rate > best_rate {
+                    best = Some((setup, m));
+                    best_rate = rate;
+                }
+            }
+        }
+        let (setup, m) = match best {
+            Some(best) => best,
+            None => {
+                let sideways = (0..SIDEWAYS_DRAWS)
+                    .map(|_| (&setups[rng.usize(..setups.len())], &macros[rng.usize(..macros.len())]))
+                    .find(|(setup, m)| gain(setup, m) == (0, true));
+                match sideways {
+                    Some(sideways) => sideways,
+                    None => return (None, nodes),
+                }
+            }
+        };
+
+        let undo = setup.moves.iter().rev().map(Move::inverse);
+        for m in setup.moves.iter().chain(m.moves.iter()).copied().chain(undo) {
+            puzzle.apply(m).unwrap();
+            // a setup may start with the moves the last one ended with
+            if path.last() == Some(&m.inverse()) {
+                path.pop();
+            } else {
+                path.push(m);
+            }
+        }
+    }
+    let solution = Some(path).filter(|_| puzzle.is_solved(SolveMode::Colors));
+    (solution, nodes)
+}
@@ -0,0 +1,270 @@
+//! Headless harness running `CubePlugin` without a renderer or glTF, for the
+//! integration tests. A stand-in window and camera let picking see the
+//! cursor, so mouse input goes through the game's own systems.
+
+use bevy::prelude::*;
+use bevy::app::Events;
+use bevy::input::ElementState;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseMotion};
+use bevy::input::InputPlugin;
+use bevy::asset::AssetPlugin;
+use bevy::render::camera::{Camera, CameraProjection};
+use bevy::transform::TransformPlugin;
+use bevy::window::{CursorMoved, WindowId, WindowPlugin};
+use bevy_mod_picking::{PickingCameraBundle, PickingPlugin};
+
+use crate::AppState;
+use crate::component::*;
+use crate::cube::{history, CubePlugin};
+use crate::pause::PausePlugin;
+use crate::puzzle::{color, home, Puzzle, COLOR_CHARS, LOOP_LEN, N_LOOPS};
+
+/// Frames `settle` steps, plenty for the state changes of a snap.
+const SETTLE_FRAMES: usize = 8;
+/// Snap animations end on their first tick, whatever the frame time.
+const SNAP_SECONDS: f32 = 1e-6;
+const WINDOW_WIDTH: f32 = 1280.0;
+const WINDOW_HEIGHT: f32 = 720.0;
+
+pub struct TestApp {
+    app: App,
+    /// Cursor position in the window, from the bottom left like `CursorMoved`.
+    cursor: Vec2,
+}
+
+impl TestApp {
+    /// Builds the cube and steps until it is in game.
+    pub fn new() -> Self {
+        let mut builder = App::build();
+        builder
+            .add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin::default())
+            .add_plugin(InputPlugin::default())
+            .add_plugin(WindowPlugin { add_primary_window: false, exit_on_close: false })
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .add_asset::<Texture>()
+            .add_asset::<ColorMaterial>()
+            .insert_resource(DragConfig { inertia: false, snap_seconds: SNAP_SECONDS, ..Default::default() })
+            .add_state(AppState::Setup)
+            .add_startup_system(spawn_cube.system())
+            .add_startup_system(spawn_camera.system())
+            .add_plugin(PickingPlugin)
+            .add_plugin(CubePlugin)
+            .add_plugin(PausePlugin)
+            .add_system_set(SystemSet::on_update(AppState::Setup)
+                .with_system(setup_complation.system())
+            );
+
+        let window = Window::new(
+            WindowId::primary(),
+            &WindowDescriptor::default(),
+            WINDOW_WIDTH as u32,
+            WINDOW_HEIGHT as u32,
+            1.0,
+            None,
+        );
+        builder.app.world.get_resource_mut::<Windows>().unwrap().add(window);
+
+        let mut test = TestApp { app: builder.app, cursor: Vec2::ZERO };
+        test.settle();
+        test
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    pub fn state(&self) -> AppState {
+        self.app.world.get_resource::<State<AppState>>().unwrap().current().clone()
+    }
+
+    /// Steps frames until any snap animation is over.
+    pub fn settle(&mut self) {
+        for _ in 0..SETTLE_FRAMES {
+            self.update();
+        }
+        assert_eq!(self.state(), AppState::InGame, "not settled after {} frames", SETTLE_FRAMES);
+    }
+
+    pub fn press_key(&mut self, key_code: KeyCode) {
+        for state in [ElementState::Pressed, ElementState::Released].iter() {
+            self.send(KeyboardInput { scan_code: 0, key_code: Some(key_code), state: *state });
+            self.update();
+        }
+    }
+
+    pub fn press_mouse(&mut self) {
+        self.send(MouseButtonInput { button: MouseButton::Left, state: ElementState::Pressed });
+        self.update();
+    }
+
+    pub fn release_mouse(&mut self) {
+        self.send(MouseButtonInput { button: MouseButton::Left, state: ElementState::Released });
+        self.update();
+    }
+
+    /// Moves the cursor by `delta` pixels, y down like `MouseMotion`.
+    pub fn move_mouse(&mut self, delta: Vec2) {
+        self.send(MouseMotion { delta });
+        self.set_cursor(self.cursor + Vec2::new(delta.x, -delta.y));
+        self.update();
+    }
+
+    /// Moves the cursor over the world `point` and presses the left button,
+    /// picking what is on top there.
+    pub fn grab(&mut self, point: Vec3) {
+        let world = self.world();
+        let mut query = world.query_filtered::<(&Camera, &GlobalTransform), With<MainCamera>>();
+        let (camera, transform) = query.iter(world).next().unwrap();
+        let view = transform.compute_matrix().inverse();
+        let ndc = camera.projection_matrix.project_point3(view.transform_point3(point));
+        let cursor = (ndc.truncate() + Vec2::ONE) / 2.0 * Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        self.set_cursor(cursor);
+        self.update();
+        self.press_mouse();
+    }
+
+    fn set_cursor(&mut self, position: Vec2) {
+        self.cursor = position;
+        self.send(CursorMoved { id: WindowId::primary(), position });
+    }
+
+    /// Queues an input event for the next `update`.
+    pub fn send<T: Send + Sync + 'static>(&mut self, event: T) {
+        self.app.world.get_resource_mut::<Events<T>>().unwrap().send(event);
+    }
+
+    /// Balls in the sensor called `name`, as `BallColor` letters in path order.
+    pub fn sensor(&mut self, name: &str) -> String {
+        let world = self.world();
+        let mut query = world.query::<(&Name, &BallSensor)>();
+        query.iter(world)
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, sensor)| sensor.to_string())
+            .unwrap_or_else(|| panic!("no sensor {}", name))
+    }
+
+    pub fn is_full(&mut self, name: &str) -> bool {
+        let world = self.world();
+        let mut query = world.query::<(&Name, &BallSensor)>();
+        query.iter(world).any(|(n, sensor)| n.as_str() == name && sensor.is_full())
+    }
+
+    /// The puzzle the balls rest in, as the game reads it.
+    pub fn puzzle(&mut self) -> Puzzle {
+        let world = &mut self.app.world;
+        let cube = world.get_resource::<CubeDescriptor>().unwrap().clone();
+        let mut query = world.query::<(&BallIndex, &PathHandle, &SlideHandle, &RotateHandle)>();
+        history::puzzle_of(&cube, query.iter(world)).expect("a ball is between slots")
+    }
+
+    /// Color letter of the ball in each slot a ball starts in, loop by loop
+    /// in slot order, `None` for slots no ball sits in.
+    pub fn slots(&mut self) -> Vec<Option<char>> {
+        let puzzle = self.puzzle();
+        (0..N_LOOPS)
+            .flat_map(|group| (0..LOOP_LEN).map(move |index| (group, index)))
+            .filter(|(group, index)| home(*group, *index).is_some())
+            .map(|(group, index)| puzzle.get(group, index).map(|ball| COLOR_CHARS[color(ball)]))
+            .collect()
+    }
+
+    /// Every ball sits in the slot of its own `BallIndex`.
+    pub fn balls_at_home(&mut self) -> bool {
+        self.puzzle().is_solved(SolveMode::Numbered)
+    }
+
+    pub fn cube_rotation(&mut self) -> Quat {
+        let world = self.world();
+        let mut query = world.query_filtered::<&Transform, With<Cube>>();
+        query.iter(world).next().unwrap().rotation
+    }
+
+    /// Drags the loop under `name` `pixels` down the screen, then lets go
+    /// and settles the snap.
+    pub fn drag_path(&mut self, name: &str, pixels: f32) {
+        self.grab_path(name, pixels);
+        self.release_mouse();
+        self.settle();
+    }
+
+    /// Grabs the loop under `name` on its outer side and drags it `pixels`
+    /// down the screen, still holding it. Down the outer side turns a loop
+    /// forward, like W.
+    pub fn grab_path(&mut self, name: &str, pixels: f32) {
+        let world = self.world();
+        let mut query = world.query::<(&Name, &Transform, &GlobalTransform, &BallSensor)>();
+        let (transform, global) = query.iter(world)
+            .find(|(n, _, _, _)| n.as_str() == name)
+            .map(|(_, transform, global, _)| (*transform, *global))
+            .unwrap_or_else(|| panic!("no sensor {}", name));
+        let outer_side = Vec3::X * transform.translation.x.signum();
+
+        self.grab(global.mul_vec3(outer_side));
+        self.move_mouse(Vec2::new(0.0, pixels));
+    }
+}
+
+impl Default for TestApp {
+    fn default() -> Self {
+        TestApp::new()
+    }
+}
+
+/// Stands in for the glTF scene: named `Cube` and `Block` nodes, each with a
+/// mesh child the size the procedural scene gives it.
+fn spawn_cube(
+    mut commands: Commands,
+    cube: Res<CubeDescriptor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let node = |name: &str| {
+        (Name::new(name.to_string()), Transform::identity(), GlobalTransform::identity())
+    };
+    let mut mesh_node = |shape: shape::Box| {
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape)),
+            ..Default::default()
+        }
+    };
+    let body = mesh_node(cube.body_box());
+    let blocks = [
+        ("block.slide", Block::Slide, mesh_node(cube.slide_block_box())),
+        ("block.rotate", Block::Rotate, mesh_node(cube.rotate_block_box())),
+    ];
+
+    commands.spawn_bundle(node("cube")).insert(Cube).with_children(|parent| {
+        parent.spawn_bundle(body);
+        for (name, block, mesh) in std::array::IntoIter::new(blocks) {
+            parent.spawn_bundle(node(name)).insert(block).with_children(|parent| {
+                parent.spawn_bundle(mesh);
+            });
+        }
+    });
+}
+
+/// Stands in for the scene camera where `setup_complation` puts it, with the
+/// projection the renderer would give it for the window.
+fn spawn_camera(mut commands: Commands) {
+    let mut bundle = PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..PerspectiveCameraBundle::new_3d()
+    };
+    bundle.perspective_projection.update(WINDOW_WIDTH, WINDOW_HEIGHT);
+    bundle.camera.projection_matrix = bundle.perspective_projection.get_projection_matrix();
+    commands.spawn_bundle(bundle)
+        .insert(MainCamera)
+        .insert_bundle(PickingCameraBundle::default());
+}
+
+fn setup_complation(mut state: ResMut<State<AppState>>) {
+    state.set(AppState::InGame).unwrap();
+}
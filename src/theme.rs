@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::component::*;
+use crate::util::otry;
+
+/// Look of the scene, read from a RON `.theme` file.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "3c4f7a2e-9d61-4b8e-a0f5-6e2d1c7b9a43"]
+pub struct Theme {
+    pub balls: BallTheme,
+    /// Replaces the material of the cube body when set.
+    #[serde(default)]
+    pub body: Option<MaterialTheme>,
+    #[serde(default = "default_sensor_color")]
+    pub sensor_color: [f32; 4],
+    pub clear_color: [f32; 3],
+    pub lights: LightRig,
+}
+
+#[derive(Deserialize)]
+pub struct BallTheme {
+    pub colors: [[f32; 3]; 4],
+    pub roughness: f32,
+    pub reflectance: f32,
+}
+
+#[derive(Deserialize)]
+pub struct MaterialTheme {
+    pub color: [f32; 3],
+    pub roughness: f32,
+    #[serde(default)]
+    pub metallic: f32,
+    pub reflectance: f32,
+}
+
+#[derive(Deserialize)]
+pub enum LightRig {
+    Studio,
+    Soft,
+    Dramatic,
+    Custom(Vec<LightTheme>),
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LightTheme {
+    pub position: [f32; 3],
+    pub intensity: f32,
+    #[serde(default = "default_light_color")]
+    pub color: [f32; 3],
+}
+
+fn default_sensor_color() -> [f32; 4] { [0.0, 0.0, 0.0, 0.0] }
+fn default_light_color() -> [f32; 3] { [1.0, 1.0, 1.0] }
+
+fn rgb(c: [f32; 3]) -> Color {
+    Color::rgb(c[0], c[1], c[2])
+}
+
+impl LightRig {
+    fn lights(&self) -> Vec<LightTheme> {
+        let preset = |lights: &[([f32; 3], f32)]| {
+            lights.iter()
+                .map(|&(position, intensity)| LightTheme {
+                    position,
+                    intensity,
+                    color: default_light_color(),
+                })
+                .collect()
+        };
+        match self {
+            LightRig::Studio => preset(&[
+                ([-9.0, 9.0, -8.0], 800.0),
+                ([6.0, 6.0, 10.0], 3000.0),
+                ([-9.0, 6.0, 10.0], 200.0),
+            ]),
+            LightRig::Soft => preset(&[
+                ([-8.0, 8.0, 10.0], 1200.0),
+                ([8.0, 8.0, 10.0], 1200.0),
+                ([0.0, -8.0, 10.0], 600.0),
+                ([0.0, 8.0, -10.0], 600.0),
+            ]),
+            LightRig::Dramatic => preset(&[
+                ([8.0, 10.0, 6.0], 4000.0),
+                ([-10.0, -4.0, -8.0], 300.0),
+            ]),
+            LightRig::Custom(lights) => lights.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let theme: Theme = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme"]
+    }
+}
+
+pub struct ThemeConfig {
+    pub path: String,
+    /// Spawn the theme's light rig, otherwise the host app lights the scene.
+    pub lights: bool,
+    /// Watch the asset files and apply the theme again when it is saved.
+    pub hot_reload: bool,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            path: "default.theme".to_string(),
+            lights: true,
+            hot_reload: false,
+        }
+    }
+}
+
+struct ThemeHandle(Handle<Theme>);
+
+/// Light spawned from the theme's rig.
+struct RigLight;
+
+/// Material of the cube body before the theme overrode it.
+struct OriginalMaterial(Handle<StandardMaterial>);
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<ThemeConfig>()
+            .add_startup_system(setup_theme.system())
+            .add_system(apply_theme.system());
+    }
+}
+
+fn setup_theme(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<ThemeConfig>,
+) {
+    // the browser has no file watcher
+    #[cfg(not(target_arch = "wasm32"))]
+    if config.hot_reload {
+        if let Err(e) = asset_server.watch_for_changes() {
+            warn!("no theme hot reload: {}", e);
+        }
+    }
+
+    commands.insert_resource(ThemeHandle(asset_server.load(config.path.as_str())));
+    // lit until the theme is loaded, or if it never is
+    if config.lights {
+        spawn_light_rig(&mut commands, &LightRig::Studio);
+    }
+}
+
+fn spawn_light_rig(commands: &mut Commands, rig: &LightRig) {
+    for light in rig.lights() {
+        commands
+            .spawn_bundle(LightBundle {
+                light: Light {
+                    color: rgb(light.color),
+                    intensity: light.intensity,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(light.position.into()),
+                ..Default::default()
+            })
+            .insert(RigLight);
+    }
+}
+
+/// Applies the theme when it loads or its file changes, and to the cube once it is set up.
+fn apply_theme(
+    mut commands: Commands,
+    config: Res<ThemeConfig>,
+    theme_handle: Res<ThemeHandle>,
+    themes: Res<Assets<Theme>>,
+    mut events: EventReader<AssetEvent<Theme>>,
+    mut style: ResMut<BallStyle>,
+    mut clear_color: ResMut<ClearColor>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    light_query: Query<Entity, With<RigLight>>,
+    body_query: Query<(Entity, &Handle<StandardMaterial>, Option<&OriginalMaterial>), With<CubeBody>>,
+    sensor_query: Query<(&Handle<StandardMaterial>, &SensorSlot), With<BallSensor>>,
+    added_query: Query<(), Or<(Added<CubeBody>, Added<BallSensor>)>>,
+) {
+    let theme_changed = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => *handle == theme_handle.0,
+        _ => false,
+    });
+    let cube_added = added_query.iter().next().is_some();
+    if !theme_changed && !cube_added {
+        return;
+    }
+    let theme = otry!(themes.get(&theme_handle.0));
+
+    if theme_changed {
+        for (base, &color) in style.base_colors.iter_mut().zip(theme.balls.colors.iter()) {
+            *base = rgb(color);
+        }
+        style.roughness = theme.balls.roughness;
+        style.reflectance = theme.balls.reflectance;
+        clear_color.0 = rgb(theme.clear_color);
+
+        for entity in light_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        if config.lights {
+            spawn_light_rig(&mut commands, &theme.lights);
+        }
+    }
+
+    for (entity, handle, original) in body_query.iter() {
+        let original = original.map(|o| o.0.clone()).unwrap_or_else(|| handle.clone());
+        if let Some(body) = &theme.body {
+            let material = materials.add(StandardMaterial {
+                base_color: rgb(body.color),
+                roughness: body.roughness,
+                metallic: body.metallic,
+                reflectance: body.reflectance,
+                ..Default::default()
+            });
+            commands.entity(entity)
+                .insert(material)
+                .insert(OriginalMaterial(original));
+        } else if *handle != original {
+            commands.entity(entity)
+                .insert(original)
+                .remove::<OriginalMaterial>();
+        }
+    }
+
+    // the loop sensors share one material, the window sensors are block meshes
+    let loop_sensor = sensor_query.iter().find(|(_, slot)| matches!(slot, SensorSlot::Group(_)));
+    if let Some((handle, _)) = loop_sensor {
+        if let Some(material) = materials.get_mut(handle) {
+            let [r, g, b, a] = theme.sensor_color;
+            material.base_color = Color::rgba(r, g, b, a);
+        }
+    }
+}
@@ -40,6 +40,13 @@ pub mod bezier {
             let t2 = t * t;
             c2 * c * p1 + 3.0 * c2 * t * p2 + 3.0 * c * t2 * p3 + t2 * t * p4
         }
+
+        /// Derivative of `cubic` by `t`.
+        pub fn cubic_derivative(p1: f32, p2: f32, p3: f32, p4: f32, t: f32) -> f32 {
+            debug_assert!(0.0 <= t && t <= 1.0);
+            let c = 1.0 - t;
+            3.0 * c * c * (p2 - p1) + 6.0 * c * t * (p3 - p2) + 3.0 * t * t * (p4 - p3)
+        }
     }
 
     pub fn cubic(from: &ControlPoint, to: &ControlPoint, t: f32) -> Vec3 {
@@ -50,12 +57,22 @@ pub mod bezier {
         )
     }
 
+    pub fn cubic_derivative(from: &ControlPoint, to: &ControlPoint, t: f32) -> Vec3 {
+        Vec3::new(
+            f32::cubic_derivative(from.origin.x, from.forward.x, to.backward.x, to.origin.x, t),
+            f32::cubic_derivative(from.origin.y, from.forward.y, to.backward.y, to.origin.y, t),
+            f32::cubic_derivative(from.origin.z, from.forward.z, to.backward.z, to.origin.z, t),
+        )
+    }
+
+    #[derive(Clone)]
     pub struct ControlPoint {
         pub backward: Vec3,
         pub origin: Vec3,
         pub forward: Vec3,
     }
 
+    #[derive(Clone)]
     pub struct Path<const N: usize> {
         pub cp: [ControlPoint; N],
     }
@@ -64,6 +81,17 @@ pub mod bezier {
         pub const N_POINTS: usize = N;
 
         pub fn evaluate(&self, t: f32) -> Vec3 {
+            let (from, to, it) = self.segment(t);
+            cubic(from, to, it)
+        }
+
+        /// Unit direction of travel at `t`.
+        pub fn tangent(&self, t: f32) -> Vec3 {
+            let (from, to, it) = self.segment(t);
+            cubic_derivative(from, to, it).normalize()
+        }
+
+        fn segment(&self, t: f32) -> (&ControlPoint, &ControlPoint, f32) {
             debug_assert!(t >= 0.0 && t <= 1.0);
 
             let m = t * Self::N_POINTS as f32;
@@ -71,9 +99,9 @@ pub mod bezier {
             let it = m.fract();
 
             if i >= Self::N_POINTS - 1 {
-                cubic(&self.cp[i], &self.cp[0], it)
+                (&self.cp[i], &self.cp[0], it)
             } else {
-                cubic(&self.cp[i], &self.cp[i + 1], it)
+                (&self.cp[i], &self.cp[i + 1], it)
             }
         }
 
@@ -83,6 +111,101 @@ pub mod bezier {
                 std::mem::swap(&mut cp.backward, &mut cp.forward);
             }
         }
+
+        /// Lookup table from arc length to `t`, `samples` per segment.
+        pub fn arc_length(&self, samples: usize) -> ArcLength {
+            ArcLength::new(samples * Self::N_POINTS, |t| self.evaluate(t))
+        }
+    }
+
+    /// Cumulative length at evenly spaced `t` of a closed curve, to map an arc
+    /// length fraction back to the `t` reaching it.
+    #[derive(Clone)]
+    pub struct ArcLength {
+        lengths: Vec<f32>,
+    }
+
+    impl ArcLength {
+        pub fn new(samples: usize, curve: impl Fn(f32) -> Vec3) -> Self {
+            debug_assert!(samples > 0);
+            let mut lengths = Vec::with_capacity(samples + 1);
+            let mut length = 0.0;
+            let mut last = curve(0.0);
+            lengths.push(length);
+            for i in 1..=samples {
+                let v = curve(i as f32 / samples as f32);
+                length += v.distance(last);
+                lengths.push(length);
+                last = v;
+            }
+            ArcLength { lengths }
+        }
+
+        pub fn length(&self) -> f32 {
+            *self.lengths.last().unwrap()
+        }
+
+        /// `t` at `s` of the whole length, both in `0.0..=1.0`.
+        pub fn parameter(&self, s: f32) -> f32 {
+            debug_assert!(s >= 0.0 && s <= 1.0);
+
+            let target = s * self.length();
+            let samples = self.lengths.len() - 1;
+            // first sample past the target, the table is sorted
+            let i = self.lengths.partition_point(|l| *l < target).clamp(1, samples);
+            let (l0, l1) = (self.lengths[i - 1], self.lengths[i]);
+            let it = if l1 > l0 { (target - l0) / (l1 - l0) } else { 0.0 };
+            (i as f32 - 1.0 + it) / samples as f32
+        }
+    }
+}
+
+/// Tiny bitmap digits for generated textures.
+pub mod glyph {
+    pub const WIDTH: usize = 3;
+    pub const HEIGHT: usize = 5;
+
+    // one row per entry, leftmost pixel in the highest bit
+    const DIGITS: [[u8; HEIGHT]; 10] = [
+        [7, 5, 5, 5, 7],
+        [2, 6, 2, 2, 7],
+        [7, 1, 7, 4, 7],
+        [7, 1, 7, 1, 7],
+        [5, 5, 7, 1, 1],
+        [7, 4, 7, 1, 7],
+        [7, 4, 7, 5, 7],
+        [7, 1, 1, 1, 1],
+        [7, 5, 7, 5, 7],
+        [7, 5, 7, 1, 7],
+    ];
+
+    pub fn text_width(text: &str, scale: usize) -> usize {
+        let n = text.chars().count();
+        (n * (WIDTH + 1)).saturating_sub(1) * scale
+    }
+
+    /// Calls `plot(x, y)` for every lit pixel of the digits in `text`, from
+    /// the top left. Anything but a digit is drawn as a blank.
+    pub fn draw(text: &str, scale: usize, mut plot: impl FnMut(usize, usize)) {
+        for (i, c) in text.chars().enumerate() {
+            let rows = match c.to_digit(10) {
+                Some(d) => DIGITS[d as usize],
+                None => continue,
+            };
+            let ox = i * (WIDTH + 1) * scale;
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..WIDTH {
+                    if row & (1 << (WIDTH - 1 - x)) == 0 {
+                        continue;
+                    }
+                    for sy in 0..scale {
+                        for sx in 0..scale {
+                            plot(ox + x * scale + sx, y * scale + sy);
+                        }
+                    }
+                }
+            }
+        }
     }
 }
 
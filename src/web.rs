@@ -0,0 +1,146 @@
+//! The JavaScript API of the wasm build: moves and scrambles are queued for
+//! the game, which reports back through the subscribed callbacks.
+//!
+//!     on_solved((mode, moves) => submit(moves));
+//!     scramble(20240101);
+//!     run();
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::AppState;
+use crate::component::{CommandQueue, PuzzleCommand, RestoredEvent, SolvedEvent};
+use crate::puzzle::{History, Move, Puzzle};
+
+#[derive(Clone, Copy)]
+enum Callback {
+    MoveCommitted,
+    Solved,
+    StateChanged,
+}
+
+thread_local! {
+    static COMMANDS: RefCell<VecDeque<PuzzleCommand>> = RefCell::new(VecDeque::new());
+    static STATE: RefCell<Option<String>> = RefCell::new(None);
+    static CALLBACKS: RefCell<[Vec<Function>; 3]> = RefCell::new(Default::default());
+}
+
+/// Queues a move written like `group.a+`, `slide>` or `rotate`. A move the
+/// blocks do not allow is ignored when its turn comes, like a blocked key.
+#[wasm_bindgen]
+pub fn apply_move(text: &str) -> Result<(), JsValue> {
+    let m = text.parse::<Move>().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    queue(PuzzleCommand::Move(m));
+    Ok(())
+}
+
+/// Queues a new scramble, the same one for the same `seed`, a random one
+/// without.
+#[wasm_bindgen]
+pub fn scramble(seed: Option<u32>) {
+    queue(PuzzleCommand::Scramble { seed: seed.map(u64::from) });
+}
+
+/// The state code of the balls at rest, `undefined` until the game starts.
+#[wasm_bindgen]
+pub fn state_code() -> Option<String> {
+    STATE.with(|state| state.borrow().clone())
+}
+
+/// Calls `callback(move, moves)` for every move made, `move` is `null` for a
+/// drag over several slots.
+#[wasm_bindgen]
+pub fn on_move_committed(callback: Function) {
+    subscribe(Callback::MoveCommitted, callback);
+}
+
+/// Calls `callback(mode, moves)` when the puzzle comes to solved, `mode` is
+/// `"colors"` or `"numbered"`.
+#[wasm_bindgen]
+pub fn on_solved(callback: Function) {
+    subscribe(Callback::Solved, callback);
+}
+
+/// Calls `callback(code, moves)` for every new state, undo and scrambles
+/// included.
+#[wasm_bindgen]
+pub fn on_state_changed(callback: Function) {
+    subscribe(Callback::StateChanged, callback);
+}
+
+fn queue(command: PuzzleCommand) {
+    COMMANDS.with(|commands| commands.borrow_mut().push_back(command));
+}
+
+fn subscribe(callback: Callback, function: Function) {
+    CALLBACKS.with(|callbacks| callbacks.borrow_mut()[callback as usize].push(function));
+}
+
+fn emit(callback: Callback, a: JsValue, b: JsValue) {
+    // a callback may subscribe another
+    let functions = CALLBACKS.with(|callbacks| callbacks.borrow()[callback as usize].clone());
+    for function in functions.iter() {
+        if let Err(e) = function.call2(&JsValue::NULL, &a, &b) {
+            warn!("JavaScript callback failed: {:?}", e);
+        }
+    }
+}
+
+pub struct WebPlugin;
+
+impl Plugin for WebPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_to_stage(CoreStage::PreUpdate, receive_commands.system())
+            // after the history recorded this frame
+            .add_system_to_stage(CoreStage::PostUpdate, notify.system());
+    }
+}
+
+/// Hands the commands over to the game's queue, in any state, which takes
+/// them one per frame in game.
+fn receive_commands(mut queue: ResMut<CommandQueue>) {
+    COMMANDS.with(|commands| queue.0.extend(commands.borrow_mut().drain(..)));
+}
+
+fn notify(
+    state: Res<State<AppState>>,
+    history: Res<History>,
+    mut solved_events: EventReader<SolvedEvent>,
+    mut restored_events: EventReader<RestoredEvent>,
+    mut last: Local<Option<(Puzzle, usize)>>,
+) {
+    // the history is not scrambled yet
+    if matches!(state.current(), AppState::Load | AppState::Setup) {
+        return;
+    }
+
+    let moves = history.moves();
+    let puzzle = history.current();
+    // a restore is news even to the same state
+    let is_restored = restored_events.iter().count() > 0;
+    let is_new = is_restored || last.as_ref().map_or(true, |(last_puzzle, _)| last_puzzle != puzzle);
+    if is_new {
+        if let Some((last_puzzle, last_moves)) = last.as_ref() {
+            if moves == last_moves + 1 {
+                let m = last_puzzle.move_to(puzzle)
+                    .map_or(JsValue::NULL, |m| JsValue::from_str(&m.to_string()));
+                emit(Callback::MoveCommitted, m, JsValue::from(moves as u32));
+            }
+        }
+
+        let code = puzzle.code();
+        STATE.with(|state| *state.borrow_mut() = Some(code.clone()));
+        emit(Callback::StateChanged, JsValue::from_str(&code), JsValue::from(moves as u32));
+        *last = Some((puzzle.clone(), moves));
+    }
+
+    for event in solved_events.iter() {
+        let mode = format!("{:?}", event.mode).to_lowercase();
+        emit(Callback::Solved, JsValue::from_str(&mode), JsValue::from(moves as u32));
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy::input::ElementState;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::MouseButtonInput;
+
+use magic_bean::AppState;
+use magic_bean::component::PuzzleCommand;
+use magic_bean::puzzle::Move;
+use magic_bean::testing::TestApp;
+
+const LOOP_A: std::ops::Range<usize> = 0..18;
+const LOOP_C: usize = 32;
+/// Pixels dragged along a loop to turn it one slot.
+const SLOT_PIXELS: f32 = 600.0 / 18.0;
+
+#[test]
+fn setup_fills_the_left_loops() {
+    let mut test = TestApp::new();
+
+    assert!(test.is_full("group.a"));
+    assert!(test.is_full("group.c"));
+    assert!(test.is_full("block.slide"));
+    assert!(test.is_full("block.rotate"));
+    assert_eq!(test.sensor("group.b").len(), 14);
+    assert_eq!(test.sensor("group.d").len(), 14);
+
+    let slots = test.slots();
+    assert!(slots.iter().all(Option::is_some));
+    for color in ['A', 'B', 'C', 'D'].iter() {
+        assert_eq!(slots.iter().filter(|c| **c == Some(*color)).count(), 16);
+    }
+}
+
+#[test]
+fn setup_scrambles_the_ball_numbers() {
+    let mut test = TestApp::new();
+    // colors alone would leave every number at home
+    assert!(!test.balls_at_home());
+}
+
+#[test]
+fn path_key_turns_the_loop_one_slot() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.press_key(KeyCode::W);
+    test.settle();
+
+    let after = test.slots();
+    for i in LOOP_A {
+        assert_eq!(after[(i + 1) % LOOP_A.end], before[i], "slot {}", i);
+    }
+    assert_eq!(after[LOOP_A.end..], before[LOOP_A.end..]);
+}
+
+#[test]
+fn path_keys_round_trip() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.press_key(KeyCode::W);
+    test.press_key(KeyCode::S);
+    test.settle();
+
+    assert_eq!(test.slots(), before);
+}
+
+#[test]
+fn slide_moves_the_window_across() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+    let window = test.sensor("block.slide");
+
+    test.press_key(KeyCode::D);
+    test.settle();
+    assert!(test.is_full("group.b"));
+    assert!(!test.is_full("group.a"));
+    assert_eq!(test.sensor("block.slide"), window);
+
+    test.press_key(KeyCode::A);
+    test.settle();
+    assert_eq!(test.slots(), before);
+}
+
+#[test]
+fn rotate_swaps_the_front_and_back_window() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.press_key(KeyCode::R);
+    test.settle();
+
+    let mut expected = before.clone();
+    for i in 1..3 {
+        expected.swap(i, LOOP_C + i);
+    }
+    assert_eq!(test.slots(), expected);
+}
+
+#[test]
+fn snap_settles_a_short_drag_back() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.drag_path("group.a", SLOT_PIXELS * 0.4);
+    assert_eq!(test.slots(), before);
+    assert!(test.is_full("group.a"));
+}
+
+#[test]
+fn snap_settles_a_long_drag_forward() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.drag_path("group.a", SLOT_PIXELS * 0.6);
+    let after = test.slots();
+    for i in LOOP_A {
+        assert_eq!(after[(i + 1) % LOOP_A.end], before[i], "slot {}", i);
+    }
+}
+
+#[test]
+fn mouse_drag_turns_the_right_loop_forward() {
+    let mut test = TestApp::new();
+    test.press_key(KeyCode::D);
+    test.settle();
+    let mut expected = test.puzzle();
+    expected.apply(Move::Turn { group: 1, forward: true }).unwrap();
+
+    test.drag_path("group.b", SLOT_PIXELS * 0.8);
+
+    assert_eq!(test.puzzle(), expected);
+}
+
+#[test]
+fn mouse_drag_across_the_rotate_block_swaps_the_window() {
+    let mut test = TestApp::new();
+    let mut expected = test.puzzle();
+    expected.apply(Move::Rotate).unwrap();
+
+    // between the loops only the rotate block is under the cursor
+    test.grab(Vec3::ZERO);
+    test.move_mouse(Vec2::new(80.0, 0.0));
+    test.release_mouse();
+    test.settle();
+
+    assert_eq!(test.puzzle(), expected);
+}
+
+#[test]
+fn reset_brings_every_ball_home() {
+    let mut test = TestApp::new();
+
+    test.press_key(KeyCode::P);
+    test.settle();
+    test.press_key(KeyCode::L);
+    test.settle();
+
+    assert!(test.balls_at_home());
+}
+
+#[test]
+fn mouse_drag_off_the_loops_rotates_the_cube() {
+    let mut test = TestApp::new();
+    let before = test.cube_rotation();
+
+    test.press_mouse();
+    test.move_mouse(Vec2::new(40.0, 0.0));
+    test.release_mouse();
+
+    assert!(test.cube_rotation().dot(before).abs() < 0.9999);
+}
+
+#[test]
+fn escape_on_a_snap_frame_pauses_the_animation() {
+    let mut test = TestApp::new();
+    let before = test.slots();
+
+    test.grab_path("group.a", SLOT_PIXELS * 0.6);
+    // the snap and the pause ask for a state on the same frame
+    test.send(MouseButtonInput { button: MouseButton::Left, state: ElementState::Released });
+    test.send(KeyboardInput { scan_code: 0, key_code: Some(KeyCode::Escape), state: ElementState::Pressed });
+    test.update();
+    test.update();
+    assert_eq!(test.state(), AppState::Paused);
+
+    test.send(KeyboardInput { scan_code: 0, key_code: Some(KeyCode::Escape), state: ElementState::Released });
+    test.update();
+    test.press_key(KeyCode::Escape);
+    test.settle();
+    let after = test.slots();
+    for i in LOOP_A {
+        assert_eq!(after[(i + 1) % LOOP_A.end], before[i], "slot {}", i);
+    }
+}
+
+#[test]
+fn commands_sent_together_all_move() {
+    let mut test = TestApp::new();
+    let moves = [Move::Turn { group: 0, forward: true }, Move::Turn { group: 2, forward: false }];
+    let mut expected = test.puzzle();
+    for m in moves.iter() {
+        expected.apply(*m).unwrap();
+        test.send(PuzzleCommand::Move(*m));
+    }
+    test.settle();
+
+    assert_eq!(test.puzzle(), expected);
+}
+
+#[test]
+fn commands_sent_while_paused_wait() {
+    let mut test = TestApp::new();
+    let m = Move::Slide { right: true };
+    let mut expected = test.puzzle();
+    expected.apply(m).unwrap();
+
+    test.press_key(KeyCode::Escape);
+    assert_eq!(test.state(), AppState::Paused);
+    test.send(PuzzleCommand::Move(m));
+    for _ in 0..4 {
+        test.update();
+    }
+    test.press_key(KeyCode::Escape);
+    test.settle();
+
+    assert_eq!(test.puzzle(), expected);
+}
@@ -0,0 +1,50 @@
+use magic_bean::component::{CubeDescriptor, PathHandle};
+
+/// Sub-steps summed to measure the length between two neighbour slots.
+const SUB_STEPS: usize = 32;
+
+/// Length along `evaluate` from each slot to the next one.
+fn slot_spacing(cube: &CubeDescriptor, evaluate: impl Fn(PathHandle) -> bevy::math::Vec3) -> Vec<f32> {
+    let step = cube.ball_step();
+    let slots = cube.group_capacity();
+    (0..slots)
+        .map(|i| {
+            let point = |j: usize| {
+                let t = (i * SUB_STEPS + j) as f32 * step / SUB_STEPS as f32;
+                evaluate(PathHandle::new(t))
+            };
+            (0..SUB_STEPS).map(|j| point(j).distance(point(j + 1))).sum()
+        })
+        .collect()
+}
+
+fn assert_even(spacing: &[f32], length: f32) {
+    let mean = length / spacing.len() as f32;
+    for (i, d) in spacing.iter().enumerate() {
+        assert!((d - mean).abs() < mean * 0.01, "slot {} to {} is {}, expected {}", i, i + 1, d, mean);
+    }
+}
+
+#[test]
+fn left_path_slots_are_evenly_spaced() {
+    let cube = CubeDescriptor::default();
+    let spacing = slot_spacing(&cube, |h| cube.left_path.evaluate(h));
+    assert_even(&spacing, cube.left_path.length());
+}
+
+#[test]
+fn right_path_slots_are_evenly_spaced() {
+    let cube = CubeDescriptor::default();
+    let spacing = slot_spacing(&cube, |h| cube.right_path.evaluate(h));
+    assert_even(&spacing, cube.right_path.length());
+}
+
+#[test]
+fn paths_start_at_their_first_control_point() {
+    let cube = CubeDescriptor::default();
+    let start = cube.right_path.evaluate(PathHandle::new(0.0));
+    let end = cube.right_path.evaluate(PathHandle::new(1.0 - 1e-6));
+    assert!(start.distance(end) < 1e-3);
+    let mirrored = cube.left_path.evaluate(PathHandle::new(0.0));
+    assert!((start.x + mirrored.x).abs() < 1e-4 && (start.y - mirrored.y).abs() < 1e-4);
+}
@@ -0,0 +1,101 @@
+use magic_bean::puzzle::*;
+
+const SOLVED: &str = "AABBAAAAAAAAAAAAAA/....BBBBBBBBBBBBBB/CCDDCCCCCCCCCCCCCC/....DDDDDDDDDDDDDD";
+
+#[test]
+fn solved_code_round_trips() {
+    let puzzle = Puzzle::solved();
+    assert_eq!(puzzle.code(), SOLVED);
+    assert_eq!(Puzzle::from_code(SOLVED).unwrap(), puzzle);
+    assert!(puzzle.is_solved(SolveMode::Colors));
+    assert!(puzzle.is_solved(SolveMode::Numbered));
+}
+
+#[test]
+fn every_move_is_undone_by_its_inverse() {
+    let start = Puzzle::solved();
+    for m in start.legal_moves() {
+        let mut puzzle = start.clone();
+        puzzle.apply(m).unwrap();
+        assert_ne!(puzzle, start, "{}", m);
+        puzzle.apply(m.inverse()).unwrap();
+        assert_eq!(puzzle, start, "{}", m);
+    }
+}
+
+#[test]
+fn turns_need_the_loop_under_the_blocks() {
+    let mut puzzle = Puzzle::solved();
+    let turn = Move::Turn { group: 1, forward: true };
+    assert_eq!(puzzle.apply(turn), Err(MoveError::NotUnderBlock { group: 1 }));
+
+    puzzle.apply(Move::Slide { right: true }).unwrap();
+    assert!(!puzzle.is_block_left());
+    assert_eq!(puzzle.apply(Move::Slide { right: true }), Err(MoveError::AlreadySlid));
+    puzzle.apply(turn).unwrap();
+}
+
+#[test]
+fn rotate_swaps_the_front_and_back_window() {
+    let mut puzzle = Puzzle::solved();
+    puzzle.apply(Move::Rotate).unwrap();
+    assert_eq!(&puzzle.code()[..4], "ACDB");
+    assert!(!puzzle.is_solved(SolveMode::Colors));
+}
+
+#[test]
+fn seeded_scrambles_repeat() {
+    let scramble = |seed| {
+        let mut puzzle = Puzzle::solved();
+        puzzle.scramble(&fastrand::Rng::with_seed(seed), SCRAMBLE_MOVES);
+        puzzle
+    };
+    let puzzle = scramble(7);
+    assert_eq!(puzzle, scramble(7));
+    assert!(puzzle.is_block_left());
+    assert_eq!(Puzzle::from_code(&puzzle.code()).unwrap().code(), puzzle.code());
+}
+
+#[test]
+fn history_steps_back() {
+    let mut history = History::default();
+    let mut puzzle = history.current().clone();
+    puzzle.apply(Move::Rotate).unwrap();
+    history.push(puzzle.clone());
+    history.push(puzzle);
+    assert_eq!(history.moves(), 1);
+
+    assert_eq!(history.undo(), Some(&Puzzle::solved()));
+    assert_eq!(history.undo(), None);
+}
+
+#[test]
+fn bad_codes_are_rejected() {
+    assert!(Puzzle::from_code("AAAA").is_err());
+    let gaps_on_both_sides = SOLVED.replacen("AABB", "....", 1);
+    assert!(Puzzle::from_code(&gaps_on_both_sides).is_err());
+}
+
+#[test]
+fn sensors_parse_like_codes() {
+    let sensors = "group.b=BBBBBBBBBBBBBB, AABBAAAAAAAAAAAAAA CCDDCCCCCCCCCCCCCC DDDDDDDDDDDDDD";
+    assert!(Puzzle::from_sensors(sensors).is_err());
+
+    let sensors = "AABBAAAAAAAAAAAAAA BBBBBBBBBBBBBB CCDDCCCCCCCCCCCCCC d=DDDDDDDDDDDDDD";
+    assert_eq!(Puzzle::from_sensors(sensors).unwrap(), Puzzle::solved());
+}
+
+#[test]
+fn moves_parse_as_they_print() {
+    let start = Puzzle::solved();
+    for m in start.legal_moves() {
+        assert_eq!(m.to_string().parse::<Move>().unwrap(), m);
+
+        let mut puzzle = start.clone();
+        puzzle.apply(m).unwrap();
+        assert_eq!(start.move_to(&puzzle), Some(m));
+    }
+    assert!("group.e+".parse::<Move>().is_err());
+    assert!("slide".parse::<Move>().is_err());
+    assert_eq!(start.move_to(&start), None);
+}
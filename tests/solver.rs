@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use magic_bean::puzzle::*;
+use magic_bean::solver::{solve, Limits};
+
+const SCRAMBLE_LEN: usize = 6;
+
+fn scrambled(seed: u64, moves: usize) -> Puzzle {
+    let mut puzzle = Puzzle::solved();
+    puzzle.scramble(&fastrand::Rng::with_seed(seed), moves);
+    puzzle
+}
+
+fn solves(start: &Puzzle, solution: &[Move]) -> bool {
+    let mut puzzle = start.clone();
+    solution.iter().all(|m| puzzle.apply(*m).is_ok()) && puzzle.is_solved(SolveMode::Colors)
+}
+
+#[test]
+fn solved_needs_no_moves() {
+    let report = solve(&Puzzle::solved(), &Limits::default());
+    assert_eq!(report.solution, Some(Vec::new()));
+    assert!(report.is_optimal);
+}
+
+#[test]
+fn short_scramble_is_solved_in_as_many_moves() {
+    let start = scrambled(7, SCRAMBLE_LEN);
+    let report = solve(&start, &Limits::default());
+    let solution = report.solution.expect("no solution");
+    assert!(solution.len() <= SCRAMBLE_LEN, "{} moves", solution.len());
+    assert!(solves(&start, &solution));
+    assert!(report.is_optimal);
+    assert_eq!(report.lower_bound, solution.len());
+}
+
+#[test]
+fn fallback_solves_past_the_depth() {
+    let start = scrambled(7, SCRAMBLE_MOVES);
+    let limits = Limits { time_limit: Duration::from_secs(60), max_depth: 0 };
+    let report = solve(&start, &limits);
+    let solution = report.solution.expect("no solution");
+    assert!(solves(&start, &solution));
+    assert!(!report.is_optimal);
+}
+
+#[test]
+fn fallback_ends_without_a_time_limit() {
+    let start = scrambled(7, SCRAMBLE_MOVES);
+    let limits = Limits { time_limit: Duration::from_secs(u64::MAX), max_depth: 0 };
+    let report = solve(&start, &limits);
+    let solution = report.solution.expect("no solution");
+    assert!(solves(&start, &solution));
+}